@@ -0,0 +1,27 @@
+#define _GNU_SOURCE
+#include <sched.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define ROUNDS 1000
+
+// Run as `cpu=0 affinity_c`: the kernel pins the task to CPU 0 when it's
+// spawned, so it never runs on another CPU, not even at first.
+int main()
+{
+    for (int i = 0; i < ROUNDS; i++) {
+        unsigned cpu = -1;
+        if (syscall(SYS_getcpu, &cpu, NULL, NULL) != 0) {
+            printf("affinity test: getcpu failed\n");
+            return 1;
+        }
+        if (cpu != 0) {
+            printf("affinity test: running on CPU %u\n", cpu);
+            return 1;
+        }
+        sched_yield();
+    }
+    printf("affinity test passed!\n");
+    return 0;
+}
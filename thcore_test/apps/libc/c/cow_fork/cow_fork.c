@@ -0,0 +1,66 @@
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define BUF_SIZE (100UL << 20)
+#define PAGE_SIZE 4096
+
+static long elapsed_ms(struct timespec *start, struct timespec *end)
+{
+    return (end->tv_sec - start->tv_sec) * 1000 + (end->tv_nsec - start->tv_nsec) / 1000000;
+}
+
+int main()
+{
+    char *buf = mmap(NULL, BUF_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (buf == MAP_FAILED) {
+        printf("cow fork test: mmap failed\n");
+        return 1;
+    }
+    // Make every page resident, so an eager copy would need another 100 MB.
+    for (unsigned long off = 0; off < BUF_SIZE; off += PAGE_SIZE)
+        buf[off] = 'p';
+
+    struct timespec start, end;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    pid_t pid = fork();
+    if (pid < 0) {
+        printf("cow fork test: fork failed\n");
+        return 1;
+    }
+    if (pid == 0) {
+        for (unsigned long off = 0; off < BUF_SIZE; off += 16 * PAGE_SIZE) {
+            if (buf[off] != 'p')
+                _exit(1);
+            buf[off] = 'c';
+        }
+        _exit(0);
+    }
+    clock_gettime(CLOCK_MONOTONIC, &end);
+    long fork_ms = elapsed_ms(&start, &end);
+
+    for (unsigned long off = 0; off < BUF_SIZE; off += 16 * PAGE_SIZE)
+        buf[off] = 'P';
+
+    int status = 0;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("cow fork test: child saw a modified buffer\n");
+        return 1;
+    }
+    for (unsigned long off = 0; off < BUF_SIZE; off += 16 * PAGE_SIZE) {
+        if (buf[off] != 'P') {
+            printf("cow fork test: parent saw the write of the child\n");
+            return 1;
+        }
+    }
+    if (fork_ms > 1000) {
+        printf("cow fork test: fork took %ld ms\n", fork_ms);
+        return 1;
+    }
+
+    printf("cow fork test passed!\n");
+    return 0;
+}
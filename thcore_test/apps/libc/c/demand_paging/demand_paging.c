@@ -0,0 +1,54 @@
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+
+#define REGION_SIZE (64UL << 20)
+#define TOUCH_SIZE (1UL << 20)
+#define PAGE_SIZE 4096
+#define REGIONS 4
+
+// Recurse deep enough to need more than the initial user stack.
+static int grow_stack(int depth)
+{
+    volatile char frame[1024];
+    frame[0] = (char)depth;
+    if (depth == 0)
+        return frame[0];
+    return grow_stack(depth - 1) + frame[0];
+}
+
+int main()
+{
+    // 4 x 64 MB is far more than the physical memory, so it only works if
+    // the pages are populated on demand.
+    char *regions[REGIONS];
+    for (int i = 0; i < REGIONS; i++) {
+        regions[i] = mmap(NULL, REGION_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+        if (regions[i] == MAP_FAILED) {
+            printf("demand paging test: mmap %d failed\n", i);
+            return 1;
+        }
+        for (unsigned long off = 0; off < TOUCH_SIZE; off += PAGE_SIZE)
+            regions[i][off] = (char)(off / PAGE_SIZE + i);
+    }
+
+    for (int i = 0; i < REGIONS; i++) {
+        for (unsigned long off = 0; off < TOUCH_SIZE; off += PAGE_SIZE) {
+            if (regions[i][off] != (char)(off / PAGE_SIZE + i)) {
+                printf("demand paging test: content mismatch in region %d\n", i);
+                return 1;
+            }
+        }
+        // Untouched pages read as zero.
+        if (regions[i][REGION_SIZE - 1] != 0) {
+            printf("demand paging test: untouched page is not zeroed\n");
+            return 1;
+        }
+        munmap(regions[i], REGION_SIZE);
+    }
+
+    grow_stack(256);
+
+    printf("demand paging test passed!\n");
+    return 0;
+}
@@ -0,0 +1,24 @@
+#include <errno.h>
+#include <stdio.h>
+#include <unistd.h>
+
+int main()
+{
+    // Neither number is assigned to a syscall on any architecture, the first
+    // one is inside the syscall table of the kernel and the second is not.
+    long nums[] = {511, 100000};
+    for (int i = 0; i < 2; i++) {
+        errno = 0;
+        if (syscall(nums[i]) != -1 || errno != ENOSYS) {
+            printf("enosys test: syscall %ld should fail with ENOSYS\n", nums[i]);
+            return 1;
+        }
+    }
+    // The task keeps running after calling them.
+    if (getpid() <= 0) {
+        printf("enosys test: getpid failed\n");
+        return 1;
+    }
+    printf("enosys test passed!\n");
+    return 0;
+}
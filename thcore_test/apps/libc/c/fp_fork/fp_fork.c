@@ -0,0 +1,64 @@
+#include <fenv.h>
+#include <sched.h>
+#include <stdio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define ROUNDS 1000
+
+// Runs a computation whose result depends on the rounding mode, yielding the
+// CPU to the other process on every round.
+static double accumulate(double sum, int rounds)
+{
+    for (int i = 0; i < rounds; i++) {
+        sum += 1.0 / 3.0;
+        sched_yield();
+    }
+    return sum;
+}
+
+int main()
+{
+    // Round upwards, so a child that starts with a zeroed `fcsr` rounds
+    // differently and gets another sum.
+    if (fesetround(FE_UPWARD) != 0) {
+        printf("fp fork test: fesetround failed\n");
+        return 1;
+    }
+    double expected = accumulate(0.0, 2 * ROUNDS);
+
+#ifdef __loongarch__
+    // Keep a value in the callee-saved `$fs0` across `fork()`.
+    register double pinned asm("$fs0") = 2.718281828459045;
+    asm volatile("" : "+f"(pinned));
+#else
+    double pinned = 2.718281828459045;
+#endif
+    double sum = accumulate(0.0, ROUNDS);
+    pid_t pid = fork();
+    if (pid < 0) {
+        printf("fp fork test: fork failed\n");
+        return 1;
+    }
+#ifdef __loongarch__
+    asm volatile("" : "+f"(pinned));
+#endif
+    // Both processes finish the computation started before the fork.
+    sum = accumulate(sum, ROUNDS);
+    int ok = fegetround() == FE_UPWARD && sum == expected && pinned == 2.718281828459045;
+    if (pid == 0)
+        _exit(ok ? 0 : 1);
+
+    int status = 0;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fp fork test: child lost its floating-point state\n");
+        return 1;
+    }
+    if (!ok) {
+        printf("fp fork test: parent lost its floating-point state\n");
+        return 1;
+    }
+
+    printf("fp fork test passed!\n");
+    return 0;
+}
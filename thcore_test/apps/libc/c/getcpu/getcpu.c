@@ -0,0 +1,41 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+int main()
+{
+    unsigned cpu = -1, node = -1;
+    if (syscall(SYS_getcpu, &cpu, &node, NULL) != 0) {
+        printf("getcpu test: getcpu failed\n");
+        return 1;
+    }
+    if (cpu == (unsigned)-1 || node != 0) {
+        printf("getcpu test: bad result cpu=%u node=%u\n", cpu, node);
+        return 1;
+    }
+    // Only the CPU is required, the node can be omitted.
+    if (syscall(SYS_getcpu, &cpu, NULL, NULL) != 0 || sched_getcpu() < 0) {
+        printf("getcpu test: getcpu without node failed\n");
+        return 1;
+    }
+
+    // Pin to CPU 1, which only exists in SMP builds.
+    cpu_set_t set;
+    CPU_ZERO(&set);
+    CPU_SET(1, &set);
+    if (sched_setaffinity(0, sizeof(set), &set) == 0) {
+        if (syscall(SYS_getcpu, &cpu, NULL, NULL) != 0 || cpu != 1) {
+            printf("getcpu test: running on CPU %u after pinned to CPU 1\n", cpu);
+            return 1;
+        }
+        printf("getcpu test: pinned to CPU 1\n");
+    } else if (errno != EINVAL) {
+        printf("getcpu test: sched_setaffinity failed\n");
+        return 1;
+    }
+    printf("getcpu test passed!\n");
+    return 0;
+}
@@ -0,0 +1,14 @@
+#include <stdio.h>
+#include <unistd.h>
+
+// Never exits, so that the harness has to kill it on timeout. A child is
+// forked to check that the whole session is killed, not only the testcase.
+int main()
+{
+    printf("hang test started\n");
+    fflush(stdout);
+    fork();
+    for (;;) {
+    }
+    return 0;
+}
@@ -0,0 +1,39 @@
+#include <stdio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+int main()
+{
+    pid_t pid = fork();
+    if (pid < 0) {
+        printf("illegal instruction test: fork failed\n");
+        return 1;
+    }
+    if (pid == 0) {
+#if defined(__loongarch__)
+        // Not a valid instruction, which raises the INE exception.
+        __asm__ volatile(".word 0xffffffff");
+#elif defined(__x86_64__)
+        __asm__ volatile("ud2");
+#elif defined(__aarch64__)
+        __asm__ volatile("udf #0");
+#elif defined(__riscv)
+        __asm__ volatile("unimp");
+#endif
+        printf("illegal instruction test: instruction executed\n");
+        _exit(0);
+    }
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid) {
+        printf("illegal instruction test: waitpid failed\n");
+        return 1;
+    }
+    // Only the child is killed, the kernel and this process keep running.
+    if (WIFEXITED(status) && WEXITSTATUS(status) == 0) {
+        printf("illegal instruction test: child exited normally\n");
+        return 1;
+    }
+    printf("illegal instruction test passed!\n");
+    return 0;
+}
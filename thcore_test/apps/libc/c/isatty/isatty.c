@@ -0,0 +1,50 @@
+#include <errno.h>
+#include <stdio.h>
+#include <sys/ioctl.h>
+#include <termios.h>
+#include <unistd.h>
+
+static int fail(const char *msg)
+{
+    printf("isatty test: %s\n", msg);
+    return 1;
+}
+
+int main()
+{
+    if (isatty(1) != 1) {
+        return fail("stdout is not a tty");
+    }
+    struct termios attr;
+    if (tcgetattr(1, &attr) != 0 || !(attr.c_lflag & ICANON)) {
+        return fail("tcgetattr failed");
+    }
+    if (tcsetattr(1, TCSANOW, &attr) != 0) {
+        return fail("tcsetattr failed");
+    }
+
+    struct winsize ws;
+    if (ioctl(1, TIOCGWINSZ, &ws) != 0 || ws.ws_row != 24 || ws.ws_col != 80) {
+        return fail("wrong default window size");
+    }
+    struct winsize new_ws = {.ws_row = 50, .ws_col = 132};
+    if (ioctl(1, TIOCSWINSZ, &new_ws) != 0) {
+        return fail("TIOCSWINSZ failed");
+    }
+    if (ioctl(1, TIOCGWINSZ, &ws) != 0 || ws.ws_row != 50 || ws.ws_col != 132) {
+        return fail("window size not stored");
+    }
+    ioctl(1, TIOCSWINSZ, &(struct winsize){.ws_row = 24, .ws_col = 80});
+
+    int fds[2];
+    if (pipe(fds) != 0) {
+        return fail("pipe failed");
+    }
+    errno = 0;
+    if (isatty(fds[0]) != 0 || errno != ENOTTY) {
+        return fail("pipe is a tty");
+    }
+
+    printf("isatty test passed!\n");
+    return 0;
+}
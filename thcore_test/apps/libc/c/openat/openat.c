@@ -0,0 +1,84 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+int main()
+{
+    const char *dir = "/openat_test";
+
+    if (mkdir(dir, 0755) != 0) {
+        printf("openat test: mkdir failed, errno %d\n", errno);
+        return 1;
+    }
+    int dfd = open(dir, O_RDONLY | O_DIRECTORY);
+    if (dfd < 0) {
+        printf("openat test: failed to open %s, errno %d\n", dir, errno);
+        return 1;
+    }
+
+    // A file created through the dirfd must appear in that directory.
+    int fd = openat(dfd, "file", O_CREAT | O_WRONLY, 0644);
+    if (fd < 0 || write(fd, "at", 2) != 2) {
+        printf("openat test: openat with a dirfd failed, errno %d\n", errno);
+        return 1;
+    }
+    close(fd);
+    struct stat st;
+    if (stat("/openat_test/file", &st) != 0 || st.st_size != 2) {
+        printf("openat test: file not created in the directory\n");
+        return 1;
+    }
+    if (faccessat(AT_FDCWD, "/file", F_OK, 0) == 0) {
+        printf("openat test: file created in the wrong directory\n");
+        return 1;
+    }
+
+    if (mkdirat(dfd, "sub", 0755) != 0 || faccessat(AT_FDCWD, "/openat_test/sub", F_OK, 0) != 0) {
+        printf("openat test: mkdirat with a dirfd failed, errno %d\n", errno);
+        return 1;
+    }
+    if (renameat(dfd, "file", dfd, "renamed") != 0 || faccessat(dfd, "renamed", F_OK, 0) != 0
+        || faccessat(dfd, "file", F_OK, 0) == 0) {
+        printf("openat test: renameat with a dirfd failed, errno %d\n", errno);
+        return 1;
+    }
+
+    // An absolute path ignores the dirfd, even an invalid one.
+    if (faccessat(-1, "/openat_test/renamed", F_OK, 0) != 0) {
+        printf("openat test: absolute path should ignore the dirfd\n");
+        return 1;
+    }
+    if (openat(-1, "renamed", O_RDONLY) >= 0 || errno != EBADF) {
+        printf("openat test: a bad dirfd should fail with EBADF\n");
+        return 1;
+    }
+    fd = openat(dfd, "renamed", O_RDONLY);
+    if (openat(fd, "x", O_RDONLY) >= 0 || errno != ENOTDIR) {
+        printf("openat test: a file as dirfd should fail with ENOTDIR\n");
+        return 1;
+    }
+    close(fd);
+
+    if (unlinkat(dfd, "sub", 0) == 0 || errno != EISDIR) {
+        printf("openat test: unlinkat on a directory should fail with EISDIR\n");
+        return 1;
+    }
+    if (unlinkat(dfd, "renamed", 0) != 0 || unlinkat(dfd, "sub", AT_REMOVEDIR) != 0) {
+        printf("openat test: unlinkat with a dirfd failed, errno %d\n", errno);
+        return 1;
+    }
+    if (stat("/openat_test/renamed", &st) == 0 || errno != ENOENT) {
+        printf("openat test: file not removed from the directory\n");
+        return 1;
+    }
+    close(dfd);
+    if (unlinkat(AT_FDCWD, dir, AT_REMOVEDIR) != 0) {
+        printf("openat test: failed to remove %s, errno %d\n", dir, errno);
+        return 1;
+    }
+
+    printf("openat test passed!\n");
+    return 0;
+}
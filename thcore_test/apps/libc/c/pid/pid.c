@@ -0,0 +1,82 @@
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static int fail(const char *msg)
+{
+    printf("pid test: %s\n", msg);
+    return 1;
+}
+
+int main()
+{
+    pid_t self = getpid();
+    if (syscall(SYS_gettid) != self) {
+        return fail("gettid differs from getpid");
+    }
+    pid_t pgid = getpgid(0);
+    if (pgid <= 0 || getsid(0) <= 0) {
+        return fail("getpgid or getsid failed");
+    }
+
+    int fds[2];
+    if (pipe(fds) != 0) {
+        return fail("pipe failed");
+    }
+    pid_t child = fork();
+    if (child < 0) {
+        return fail("fork failed");
+    }
+    if (child == 0) {
+        // The child starts in the process group of the parent.
+        if (getppid() != self || getpgid(0) != pgid || getsid(0) != getsid(self)) {
+            exit(1);
+        }
+        if (setpgid(0, 0) != 0 || getpgid(0) != getpid()) {
+            exit(2);
+        }
+        close(fds[0]);
+        write(fds[1], "x", 1);
+        exit(7);
+    }
+
+    // Wait until the child has exited without reaping it.
+    char c;
+    close(fds[1]);
+    if (read(fds[0], &c, 1) != 1) {
+        return fail("no message from the child");
+    }
+    struct timespec ts = {0, 50 * 1000 * 1000};
+    nanosleep(&ts, NULL);
+    if (getpgid(child) != child) {
+        return fail("the exited child is not found before reaped");
+    }
+    int status;
+    if (waitpid(child, &status, 0) != child || !WIFEXITED(status) || WEXITSTATUS(status) != 7) {
+        return fail("wrong exit status of the child");
+    }
+    errno = 0;
+    if (getpgid(child) != -1 || errno != ESRCH) {
+        return fail("the child is found after reaped");
+    }
+    errno = 0;
+    if (setpgid(getppid(), 0) != -1 || errno != ESRCH) {
+        return fail("setpgid on a non-child succeeded");
+    }
+
+    // A new PID is not the one just freed.
+    pid_t child2 = fork();
+    if (child2 == 0) {
+        exit(0);
+    }
+    if (child2 < 0 || child2 == child || waitpid(child2, NULL, 0) != child2) {
+        return fail("the PID is reused immediately");
+    }
+
+    printf("pid test passed!\n");
+    return 0;
+}
@@ -0,0 +1,99 @@
+#define _GNU_SOURCE
+#include <poll.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/select.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static int fail(const char *msg)
+{
+    printf("poll test: %s\n", msg);
+    return 1;
+}
+
+int main()
+{
+    int fds[2];
+    if (pipe(fds) != 0) {
+        return fail("pipe failed");
+    }
+
+    // Nothing to read yet, a zero timeout returns immediately.
+    struct timespec zero = {0, 0};
+    struct pollfd pfds[3] = {
+        {.fd = fds[0], .events = POLLIN},
+        {.fd = -1, .events = POLLIN},
+        {.fd = 100, .events = POLLIN},
+    };
+    if (ppoll(pfds, 1, &zero, NULL) != 0 || pfds[0].revents != 0) {
+        return fail("empty pipe should not be readable");
+    }
+    // Negative fds are ignored, closed fds get POLLNVAL.
+    if (ppoll(pfds, 3, &zero, NULL) != 1 || pfds[1].revents != 0 || pfds[2].revents != POLLNVAL) {
+        return fail("wrong revents for negative or closed fds");
+    }
+    pfds[1].fd = fds[1];
+    pfds[1].events = POLLOUT;
+    if (ppoll(pfds + 1, 1, &zero, NULL) != 1 || pfds[1].revents != POLLOUT) {
+        return fail("empty pipe should be writable");
+    }
+
+    pid_t pid = fork();
+    if (pid < 0) {
+        return fail("fork failed");
+    }
+    if (pid == 0) {
+        close(fds[0]);
+        usleep(100000);
+        write(fds[1], "ping", 4);
+        close(fds[1]);
+        _exit(0);
+    }
+
+    // Wait for the delayed writer.
+    struct timespec timeout = {5, 0};
+    if (ppoll(pfds, 1, &timeout, NULL) != 1 || pfds[0].revents != POLLIN) {
+        return fail("pipe should become readable");
+    }
+
+    fd_set rfds;
+    FD_ZERO(&rfds);
+    FD_SET(fds[0], &rfds);
+    if (pselect(fds[0] + 1, &rfds, NULL, NULL, &zero, NULL) != 1 || !FD_ISSET(fds[0], &rfds)) {
+        return fail("pselect should report the readable pipe");
+    }
+
+    char buf[16] = {0};
+    if (read(fds[0], buf, sizeof(buf)) != 4 || strcmp(buf, "ping") != 0) {
+        return fail("wrong data read");
+    }
+    waitpid(pid, NULL, 0);
+
+    // Once the writer is closed, the reader is hung up, even if POLLHUP is
+    // not requested.
+    close(fds[1]);
+    if (ppoll(pfds, 1, &timeout, NULL) != 1 || !(pfds[0].revents & POLLHUP)) {
+        return fail("closed pipe should be hung up");
+    }
+    FD_ZERO(&rfds);
+    FD_SET(fds[0], &rfds);
+    if (pselect(fds[0] + 1, &rfds, NULL, NULL, &zero, NULL) != 1 || !FD_ISSET(fds[0], &rfds)) {
+        return fail("pselect should report the hung up pipe as readable");
+    }
+    close(fds[0]);
+
+    // A write end without a reader gets POLLERR.
+    if (pipe(fds) != 0) {
+        return fail("pipe failed");
+    }
+    close(fds[0]);
+    struct pollfd wpfd = {.fd = fds[1], .events = 0};
+    if (ppoll(&wpfd, 1, &zero, NULL) != 1 || !(wpfd.revents & POLLERR)) {
+        return fail("pipe without a reader should get POLLERR");
+    }
+    close(fds[1]);
+    printf("poll test passed!\n");
+    return 0;
+}
@@ -0,0 +1,62 @@
+#define _GNU_SOURCE
+#include <sched.h>
+#include <stdio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// How many rounds the child spins, many time slices even on a fast CPU.
+#define SPIN_ROUNDS (1UL << 29)
+
+// Keeps the calling task on CPU 0, so that the parent can only run while the
+// child is off the CPU.
+static int pin_to_cpu0(void)
+{
+    cpu_set_t set;
+    CPU_ZERO(&set);
+    CPU_SET(0, &set);
+    return sched_setaffinity(0, sizeof(set), &set);
+}
+
+int main()
+{
+    int fds[2];
+    if (pin_to_cpu0() != 0 || pipe(fds) != 0) {
+        printf("preempt test: setup failed\n");
+        return 1;
+    }
+    pid_t pid = fork();
+    if (pid < 0) {
+        printf("preempt test: fork failed\n");
+        return 1;
+    }
+    if (pid == 0) {
+        if (pin_to_cpu0() != 0 || write(fds[1], "s", 1) != 1)
+            _exit(1);
+        // Spin in user space without any syscall, only a timer interrupt can
+        // take the CPU away from this task.
+        volatile unsigned long counter = 0;
+        while (counter < SPIN_ROUNDS)
+            counter++;
+        _exit(0);
+    }
+
+    // Wait until the child has started spinning. Without preemption, the
+    // parent would only get the CPU back after the child has exited.
+    char c;
+    if (read(fds[0], &c, 1) != 1) {
+        printf("preempt test: the child failed to start\n");
+        return 1;
+    }
+    int status;
+    if (waitpid(pid, &status, WNOHANG) != 0) {
+        printf("preempt test: the spinning child was never preempted\n");
+        return 1;
+    }
+
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("preempt test: wait failed\n");
+        return 1;
+    }
+    printf("preempt test passed!\n");
+    return 0;
+}
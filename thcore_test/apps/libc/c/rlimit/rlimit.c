@@ -0,0 +1,56 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+#define NOFILE 8
+
+int main()
+{
+    struct rlimit rlim;
+    if (getrlimit(RLIMIT_STACK, &rlim) != 0 || rlim.rlim_cur == 0 || rlim.rlim_cur > rlim.rlim_max) {
+        printf("rlimit test: bad RLIMIT_STACK\n");
+        return 1;
+    }
+
+    // `prlimit` is used instead of `setrlimit`, which synchronizes all the
+    // threads with signals in musl.
+    struct rlimit nofile = {NOFILE, NOFILE};
+    if (prlimit(0, RLIMIT_NOFILE, &nofile, NULL) != 0) {
+        printf("rlimit test: failed to lower RLIMIT_NOFILE\n");
+        return 1;
+    }
+    if (getrlimit(RLIMIT_NOFILE, &rlim) != 0 || rlim.rlim_cur != NOFILE || rlim.rlim_max != NOFILE) {
+        printf("rlimit test: RLIMIT_NOFILE not updated\n");
+        return 1;
+    }
+    struct rlimit raise = {NOFILE, NOFILE + 1};
+    if (prlimit(0, RLIMIT_NOFILE, &raise, NULL) != -1 || errno != EPERM) {
+        printf("rlimit test: raising the hard limit should fail with EPERM\n");
+        return 1;
+    }
+
+    // fd 0, 1 and 2 are taken by stdio, so only 5 more files can be opened.
+    int fd = -1;
+    for (int i = 3; i < NOFILE; i++) {
+        fd = open("rlimit_test_file", O_RDWR | O_CREAT, 0644);
+        if (fd != i) {
+            printf("rlimit test: open returned %d, expected %d\n", fd, i);
+            return 1;
+        }
+    }
+    if (open("rlimit_test_file", O_RDWR) != -1 || errno != EMFILE) {
+        printf("rlimit test: open beyond RLIMIT_NOFILE should fail with EMFILE\n");
+        return 1;
+    }
+    if (dup(fd) != -1 || errno != EMFILE) {
+        printf("rlimit test: dup beyond RLIMIT_NOFILE should fail with EMFILE\n");
+        return 1;
+    }
+    close(fd);
+    unlink("rlimit_test_file");
+    printf("rlimit test passed!\n");
+    return 0;
+}
@@ -0,0 +1,94 @@
+#include <stdio.h>
+#include <sys/resource.h>
+#include <sys/times.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static long elapsed_us(const struct timespec *start)
+{
+    struct timespec now;
+    clock_gettime(CLOCK_MONOTONIC, &now);
+    return (now.tv_sec - start->tv_sec) * 1000000L + (now.tv_nsec - start->tv_nsec) / 1000;
+}
+
+static long tv_us(const struct timeval *tv)
+{
+    return tv->tv_sec * 1000000L + tv->tv_usec;
+}
+
+// Spin in the user space for `us` microseconds of wall-clock time.
+static void burn(long us)
+{
+    struct timespec start;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    volatile unsigned long x = 0;
+    while (elapsed_us(&start) < us) {
+        for (int i = 0; i < 10000; i++) {
+            x += i;
+        }
+    }
+}
+
+int main()
+{
+    struct rusage before, after;
+    struct timespec start;
+
+    // The CPU time of a spinning process is close to its wall-clock time.
+    getrusage(RUSAGE_SELF, &before);
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    burn(500000);
+    long wall = elapsed_us(&start);
+    getrusage(RUSAGE_SELF, &after);
+    long utime = tv_us(&after.ru_utime) - tv_us(&before.ru_utime);
+    long stime = tv_us(&after.ru_stime) - tv_us(&before.ru_stime);
+    if (utime < wall / 2 || utime + stime > wall + 20000) {
+        printf("rusage test: burned %ld us, utime %ld us, stime %ld us\n", wall, utime, stime);
+        return 1;
+    }
+    if (after.ru_maxrss <= 0) {
+        printf("rusage test: maxrss %ld\n", after.ru_maxrss);
+        return 1;
+    }
+
+    // A sleeping process uses almost no CPU time.
+    getrusage(RUSAGE_SELF, &before);
+    usleep(300000);
+    getrusage(RUSAGE_SELF, &after);
+    utime = tv_us(&after.ru_utime) - tv_us(&before.ru_utime);
+    stime = tv_us(&after.ru_stime) - tv_us(&before.ru_stime);
+    if (utime + stime > 50000) {
+        printf("rusage test: slept with utime %ld us, stime %ld us\n", utime, stime);
+        return 1;
+    }
+
+    // The CPU time of a reaped child is added to the children times.
+    pid_t pid = fork();
+    if (pid == 0) {
+        burn(200000);
+        _exit(0);
+    }
+    int status;
+    if (pid < 0 || waitpid(pid, &status, 0) != pid) {
+        printf("rusage test: fork or waitpid failed\n");
+        return 1;
+    }
+    struct rusage children;
+    getrusage(RUSAGE_CHILDREN, &children);
+    if (tv_us(&children.ru_utime) < 100000) {
+        printf("rusage test: children utime %ld us\n", tv_us(&children.ru_utime));
+        return 1;
+    }
+
+    struct tms tms;
+    long ticks = sysconf(_SC_CLK_TCK);
+    if (times(&tms) == (clock_t)-1 || tms.tms_utime < ticks / 4 || tms.tms_cutime < ticks / 10) {
+        printf("rusage test: times utime %ld cutime %ld ticks\n", (long)tms.tms_utime,
+               (long)tms.tms_cutime);
+        return 1;
+    }
+
+    printf("rusage test passed!\n");
+    return 0;
+}
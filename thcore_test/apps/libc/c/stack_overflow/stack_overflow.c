@@ -0,0 +1,52 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static int recurse(int depth)
+{
+    volatile char buf[1024];
+    buf[0] = (char)depth;
+    return recurse(depth + 1) + buf[0];
+}
+
+int main()
+{
+    pid_t pid = fork();
+    if (pid < 0) {
+        printf("stack overflow test: fork failed\n");
+        return 1;
+    }
+    if (pid == 0) {
+        // Raise the stack limit as far as allowed, which covers the whole
+        // region reserved for the stack, so that the overflow faults in the
+        // guard region below it rather than at the limit.
+        struct rlimit rlim;
+        if (getrlimit(RLIMIT_STACK, &rlim) != 0) {
+            printf("stack overflow test: getrlimit failed\n");
+            _exit(1);
+        }
+        rlim.rlim_cur = rlim.rlim_max;
+        if (prlimit(0, RLIMIT_STACK, &rlim, NULL) != 0) {
+            printf("stack overflow test: prlimit failed\n");
+            _exit(1);
+        }
+        recurse(0);
+        printf("stack overflow test: recursion returned\n");
+        _exit(0);
+    }
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid) {
+        printf("stack overflow test: waitpid failed\n");
+        return 1;
+    }
+    // The child must be killed, either by a signal or with a failure code.
+    if (WIFEXITED(status) && WEXITSTATUS(status) == 0) {
+        printf("stack overflow test: child exited normally\n");
+        return 1;
+    }
+    printf("stack overflow test passed!\n");
+    return 0;
+}
@@ -0,0 +1,49 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+int main()
+{
+    const char *path = "/stat_test.txt";
+    struct stat st;
+
+    int fd = open(path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0 || write(fd, "12345", 5) != 5) {
+        printf("stat test: failed to create %s\n", path);
+        return 1;
+    }
+
+    if (fstat(fd, &st) != 0 || st.st_size != 5 || !S_ISREG(st.st_mode)) {
+        printf("stat test: fstat mismatch, size %ld mode %o\n", (long)st.st_size, st.st_mode);
+        return 1;
+    }
+    close(fd);
+
+    if (stat(path, &st) != 0 || st.st_size != 5 || !S_ISREG(st.st_mode) || st.st_nlink < 1) {
+        printf("stat test: stat mismatch, size %ld mode %o\n", (long)st.st_size, st.st_mode);
+        return 1;
+    }
+
+    if (fstatat(AT_FDCWD, "/", &st, 0) != 0 || !S_ISDIR(st.st_mode)) {
+        printf("stat test: fstatat on a directory failed\n");
+        return 1;
+    }
+
+    // An empty path with AT_FDCWD refers to the current directory.
+    if (chdir("/") != 0 || fstatat(AT_FDCWD, "", &st, AT_EMPTY_PATH) != 0 || !S_ISDIR(st.st_mode)) {
+        printf("stat test: fstatat on the current directory failed\n");
+        return 1;
+    }
+
+    if (stat("/no/such/file", &st) == 0 || errno != ENOENT) {
+        printf("stat test: missing file should fail with ENOENT\n");
+        return 1;
+    }
+    unlink(path);
+
+    printf("stat test passed!\n");
+    return 0;
+}
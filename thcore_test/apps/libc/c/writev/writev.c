@@ -0,0 +1,79 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+int main()
+{
+    const char *path = "/writev_test.txt";
+    char header[] = "header:";
+    char payload[] = "payload";
+    char expect[] = "header:payload";
+    char buf[32] = {0};
+
+    int fd = open(path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        printf("writev test: open failed\n");
+        return 1;
+    }
+
+    struct iovec wv[3] = {
+        {.iov_base = header, .iov_len = strlen(header)},
+        {.iov_base = NULL, .iov_len = 0},
+        {.iov_base = payload, .iov_len = strlen(payload)},
+    };
+    ssize_t n = writev(fd, wv, 3);
+    if (n != (ssize_t)strlen(expect)) {
+        printf("writev test: writev returned %ld\n", (long)n);
+        return 1;
+    }
+
+    close(fd);
+
+    fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        printf("writev test: reopen failed\n");
+        return 1;
+    }
+    char first[7], second[16];
+    struct iovec rv[2] = {
+        {.iov_base = first, .iov_len = sizeof(first)},
+        {.iov_base = second, .iov_len = sizeof(second)},
+    };
+    n = readv(fd, rv, 2);
+    if (n != (ssize_t)strlen(expect)) {
+        printf("writev test: readv returned %ld\n", (long)n);
+        return 1;
+    }
+    memcpy(buf, first, sizeof(first));
+    memcpy(buf + sizeof(first), second, n - sizeof(first));
+
+    // The gathered output must be identical to a single write of the same bytes.
+    if (memcmp(buf, expect, strlen(expect)) != 0) {
+        printf("writev test: content mismatch: %s\n", buf);
+        return 1;
+    }
+
+    // Reading into a buffer that can't be written fails.
+    char *readonly = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (readonly == MAP_FAILED) {
+        printf("writev test: mmap failed\n");
+        return 1;
+    }
+    lseek(fd, 0, SEEK_SET);
+    rv[0].iov_base = readonly;
+    n = readv(fd, rv, 2);
+    if (n != -1 || errno != EFAULT) {
+        printf("writev test: readv into a read-only buffer returned %ld\n", (long)n);
+        return 1;
+    }
+    munmap(readonly, 4096);
+    close(fd);
+    unlink(path);
+
+    printf("writev test passed!\n");
+    return 0;
+}
@@ -1,5 +1,10 @@
 use alloc::sync::Arc;
+#[cfg(feature = "multitask")]
+use alloc::vec::Vec;
 use core::ffi::c_int;
+#[cfg(feature = "multitask")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
@@ -20,22 +25,150 @@ pub trait FileLike: Send + Sync {
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync>;
     fn poll(&self) -> LinuxResult<PollState>;
     fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult;
+
+    /// Get the conditions of the file that `poll` reports without being asked
+    /// for.
+    ///
+    /// Only a pipe has any of them for now, so none by default.
+    fn poll_conditions(&self) -> PollConditions {
+        PollConditions::default()
+    }
+
+    /// Get the waiters to wake up when the state returned by
+    /// [`poll`](Self::poll) changes.
+    ///
+    /// Returns `None` if the file doesn't notify any change, in which case
+    /// the waiters should poll it periodically.
+    fn poll_waiters(&self) -> Option<&PollWaiters> {
+        None
+    }
+
+    /// Handle the device-specific request `cmd` of `ioctl` with `arg`.
+    ///
+    /// Only terminals support requests for now, so it fails with `ENOTTY` by
+    /// default.
+    fn ioctl(&self, _cmd: u32, _arg: usize) -> LinuxResult<usize> {
+        Err(LinuxError::ENOTTY)
+    }
 }
 
 def_resource! {
     pub static FD_TABLE: ResArc<RwLock<FlattenObjects<Arc<dyn FileLike>, AX_FILE_LIMIT>>> = ResArc::new();
+    /// The upper bound (exclusive) of newly allocated file descriptors, i.e.,
+    /// the soft `RLIMIT_NOFILE`. It never exceeds [`AX_FILE_LIMIT`].
+    pub static FD_LIMIT: AtomicUsize = AtomicUsize::new(AX_FILE_LIMIT);
+}
+
+/// The conditions of a file returned by [`FileLike::poll_conditions`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PollConditions {
+    /// The other end is closed, i.e. `POLLHUP`.
+    pub hangup: bool,
+    /// An error occurred, e.g. the read end of a pipe is closed for its write
+    /// end, i.e. `POLLERR`.
+    pub error: bool,
+}
+
+/// A task waiting for any of a set of files to change its state.
+///
+/// It's registered in the [`PollWaiters`] of every file, and woken up by the
+/// first one that changes.
+#[cfg(feature = "multitask")]
+pub struct Poller {
+    queue: axtask::WaitQueue,
+    woken: AtomicBool,
+}
+
+#[cfg(feature = "multitask")]
+impl Poller {
+    /// Create a poller that is not woken up yet.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            queue: axtask::WaitQueue::new(),
+            woken: AtomicBool::new(false),
+        })
+    }
+
+    /// Block the current task until the poller is woken up or the task is
+    /// interrupted, and reset the poller for the next wait.
+    ///
+    /// A wake-up since the last wait returns immediately, so the files can be
+    /// polled after registering without missing a change.
+    pub fn wait(&self) {
+        self.queue.wait_until(|| self.is_woken());
+        self.woken.store(false, Ordering::Release);
+    }
+
+    /// Like [`wait`](Self::wait), but gives up after `dur`.
+    #[cfg(feature = "irq")]
+    pub fn wait_timeout(&self, dur: core::time::Duration) {
+        self.queue.wait_timeout_until(dur, || self.is_woken());
+        self.woken.store(false, Ordering::Release);
+    }
+
+    fn is_woken(&self) -> bool {
+        self.woken.load(Ordering::Acquire) || axtask::current().is_interrupted()
+    }
+
+    fn wake(&self) {
+        self.woken.store(true, Ordering::Release);
+        self.queue.notify_all(false);
+    }
+}
+
+/// The pollers waiting for a file, see [`FileLike::poll_waiters`].
+#[derive(Default)]
+pub struct PollWaiters {
+    #[cfg(feature = "multitask")]
+    pollers: spin::Mutex<Vec<Arc<Poller>>>,
+}
+
+impl PollWaiters {
+    /// Create an empty set of waiters.
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "multitask")]
+            pollers: spin::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add `poller` to be woken up by [`notify`](Self::notify).
+    #[cfg(feature = "multitask")]
+    pub fn register(&self, poller: &Arc<Poller>) {
+        self.pollers.lock().push(poller.clone());
+    }
+
+    /// Remove `poller` added by [`register`](Self::register).
+    #[cfg(feature = "multitask")]
+    pub fn unregister(&self, poller: &Arc<Poller>) {
+        self.pollers.lock().retain(|p| !Arc::ptr_eq(p, poller));
+    }
+
+    /// Wake up the pollers after the state of the file changed.
+    pub fn notify(&self) {
+        #[cfg(feature = "multitask")]
+        for poller in self.pollers.lock().iter() {
+            poller.wake();
+        }
+    }
+}
+
+impl FD_LIMIT {
+    /// Get the current limit of file descriptors.
+    pub fn get(&self) -> usize {
+        self.load(Ordering::Acquire)
+    }
+
+    /// Set the limit of file descriptors, clamped to [`AX_FILE_LIMIT`].
+    pub fn set(&self, limit: usize) {
+        self.store(limit.min(AX_FILE_LIMIT), Ordering::Release);
+    }
 }
 
 impl FD_TABLE {
     /// Return a copy of the inner table.
     pub fn copy_inner(&self) -> RwLock<FlattenObjects<Arc<dyn FileLike>, AX_FILE_LIMIT>> {
-        let table = self.read();
-        let mut new_table = FlattenObjects::new();
-        let count = table.count();
-        for i in 0..count {
-            let _ = new_table.add_at(i, table.get(i).unwrap().clone());
-        }
-        RwLock::new(new_table)
+        RwLock::new(self.read().clone())
     }
 }
 
@@ -49,8 +182,16 @@ pub fn get_file_like(fd: c_int) -> LinuxResult<Arc<dyn FileLike>> {
 }
 
 /// Add a file to the file descriptor table.
+///
+/// Fails with `EMFILE` if the lowest free descriptor is not below [`FD_LIMIT`].
 pub fn add_file_like(f: Arc<dyn FileLike>) -> LinuxResult<c_int> {
-    Ok(FD_TABLE.write().add(f).map_err(|_| LinuxError::EMFILE)? as c_int)
+    let mut table = FD_TABLE.write();
+    let fd = table.add(f).map_err(|_| LinuxError::EMFILE)?;
+    if fd >= FD_LIMIT.get() {
+        table.remove(fd);
+        return Err(LinuxError::EMFILE);
+    }
+    Ok(fd as c_int)
 }
 
 /// Close a file by `fd`.
@@ -98,7 +239,7 @@ pub fn sys_dup2(old_fd: c_int, new_fd: c_int) -> c_int {
                 return Ok(r);
             }
         }
-        if new_fd as usize >= AX_FILE_LIMIT {
+        if new_fd as usize >= FD_LIMIT.get() {
             return Err(LinuxError::EBADF);
         }
 
@@ -141,15 +282,10 @@ pub fn sys_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
 
 #[ctor_bare::register_ctor]
 fn init_stdio() {
-    let mut fd_table = flatten_objects::FlattenObjects::new();
-    fd_table
-        .add_at(0, Arc::new(stdin()) as _)
-        .unwrap_or_else(|_| panic!()); // stdin
-    fd_table
-        .add_at(1, Arc::new(stdout()) as _)
-        .unwrap_or_else(|_| panic!()); // stdout
-    fd_table
-        .add_at(2, Arc::new(stdout()) as _)
-        .unwrap_or_else(|_| panic!()); // stderr
+    let fd_table = FlattenObjects::from_iter([
+        Arc::new(stdin()) as _,  // stdin
+        Arc::new(stdout()) as _, // stdout
+        Arc::new(stdout()) as _, // stderr
+    ]);
     FD_TABLE.init_new(spin::RwLock::new(fd_table));
 }
@@ -1,3 +1,4 @@
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use core::ffi::{c_char, c_int};
@@ -36,7 +37,7 @@ impl File {
             .map_err(|_| LinuxError::EINVAL)
     }
 
-    /// Get the path of the file.
+    /// Get the absolute path of the file.
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -65,8 +66,6 @@ impl FileLike for File {
             st_ino: 1,
             st_nlink: 1,
             st_mode,
-            st_uid: 1000,
-            st_gid: 1000,
             st_size: metadata.size() as _,
             st_blocks: metadata.blocks() as _,
             st_blksize: 512,
@@ -130,14 +129,17 @@ pub fn sys_open(filename: *const c_char, flags: c_int, mode: ctypes::mode_t) ->
     debug!("sys_open <= {:?} {:#o} {:#o}", filename, flags, mode);
     syscall_body!(sys_open, {
         let options = flags_to_options(flags, mode);
+        let filename = filename?;
+        let path = axfs::api::canonicalize(filename)?;
         if options.has_directory() {
-            return Directory::from_path(filename?.into(), &options)
+            return Directory::from_path(filename, path, &options)
                 .and_then(Directory::add_to_fd_table);
         }
         add_file_or_directory_fd(
             axfs::fops::File::open,
             axfs::fops::Directory::open_dir,
-            filename?,
+            filename,
+            &path,
             &options,
         )
     })
@@ -172,10 +174,12 @@ pub fn sys_openat(
     }
 
     match Directory::from_fd(dirfd).and_then(|dir| {
+        let path = axfs::api::canonicalize(&format!("{}/{}", dir.path(), filename))?;
         add_file_or_directory_fd(
             |filename, options| dir.inner.lock().open_file_at(filename, options),
             |filename, options| dir.inner.lock().open_dir_at(filename, options),
             filename,
+            &path,
             &options,
         )
     }) {
@@ -189,10 +193,13 @@ pub fn sys_openat(
 
 /// Use the function to open file or directory, then add into file descriptor table.
 /// First try opening files, if fails, try directory.
+///
+/// `path` is the absolute path of `filename`, kept in the file descriptor table.
 fn add_file_or_directory_fd<F, D, E>(
     open_file: F,
     open_dir: D,
     filename: &str,
+    path: &str,
     options: &OpenOptions,
 ) -> LinuxResult<c_int>
 where
@@ -202,12 +209,12 @@ where
 {
     open_file(filename, options)
         .map_err(Into::into)
-        .map(|f| File::new(f, filename.into()))
+        .map(|f| File::new(f, path.into()))
         .and_then(File::add_to_fd_table)
         .or_else(|e| match e {
             LinuxError::EISDIR => open_dir(filename, options)
                 .map_err(Into::into)
-                .map(|d| Directory::new(d, filename.into()))
+                .map(|d| Directory::new(d, path.into()))
                 .and_then(Directory::add_to_fd_table),
             _ => Err(e),
         })
@@ -327,8 +334,8 @@ impl Directory {
         }
     }
 
-    fn from_path(path: String, options: &OpenOptions) -> LinuxResult<Self> {
-        axfs::fops::Directory::open_dir(&path, options)
+    fn from_path(filename: &str, path: String, options: &OpenOptions) -> LinuxResult<Self> {
+        axfs::fops::Directory::open_dir(filename, options)
             .map_err(Into::into)
             .map(|d| Self::new(d, path))
     }
@@ -345,7 +352,7 @@ impl Directory {
             .map_err(|_| LinuxError::EINVAL)
     }
 
-    /// Get the path of the directory.
+    /// Get the absolute path of the directory.
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -361,7 +368,19 @@ impl FileLike for Directory {
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
-        Err(LinuxError::EBADF)
+        let metadata = axfs::api::metadata(&self.path)?;
+        let ty = metadata.file_type() as u8;
+        let perm = metadata.permissions().bits() as u32;
+        let st_mode = ((ty as u32) << 12) | perm;
+        Ok(ctypes::stat {
+            st_ino: 1,
+            st_nlink: 1,
+            st_mode,
+            st_size: metadata.size() as _,
+            st_blocks: metadata.blocks() as _,
+            st_blksize: 512,
+            ..Default::default()
+        })
     }
 
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
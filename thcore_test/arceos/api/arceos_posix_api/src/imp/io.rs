@@ -23,7 +23,7 @@ pub fn sys_read(fd: c_int, buf: *mut c_void, count: usize) -> ctypes::ssize_t {
         }
         #[cfg(not(feature = "fd"))]
         match fd {
-            0 => Ok(super::stdio::stdin().read(dst)? as ctypes::ssize_t),
+            0 => Ok(super::stdio::stdin().read_blocked(dst)? as ctypes::ssize_t),
             1 | 2 => Err(LinuxError::EPERM),
             _ => Err(LinuxError::EBADF),
         }
@@ -361,8 +361,8 @@ fn handle_relative_path(dir_fd: isize, path: &str) -> AxResult<String> {
     }
     match super::fs::Directory::from_fd(dir_fd as i32) {
         Ok(dir) => {
-            // 假设目录路径以 '/' 结尾，无需手动添加
-            let combined_path = format!("{}{}", dir.path(), path);
+            // 目录路径是不以 '/' 结尾的绝对路径
+            let combined_path = format!("{}/{}", dir.path(), path);
             axlog::info!("处理后的路径: {} (目录: {})", combined_path, dir.path());
             Ok(combined_path)
         }
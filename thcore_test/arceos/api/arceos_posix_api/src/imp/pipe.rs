@@ -5,7 +5,7 @@ use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
 use axsync::Mutex;
 
-use super::fd_ops::{FileLike, add_file_like, close_file_like};
+use super::fd_ops::{FileLike, PollConditions, PollWaiters, add_file_like, close_file_like};
 use crate::ctypes;
 
 #[derive(Copy, Clone, PartialEq)]
@@ -77,18 +77,37 @@ impl PipeRingBuffer {
 pub struct Pipe {
     readable: bool,
     buffer: Arc<Mutex<PipeRingBuffer>>,
+    // Dropped after `buffer`, see `PipeWaiters`.
+    waiters: PipeWaiters,
+}
+
+/// The waiters of a pipe, shared by both ends, since a change made through
+/// one end is seen from the other.
+///
+/// Dropping an end notifies them, after the end has released the buffer so
+/// that the other end sees it closed.
+struct PipeWaiters(Arc<PollWaiters>);
+
+impl Drop for PipeWaiters {
+    fn drop(&mut self) {
+        // The other end sees the end of file, or can't be written any more.
+        self.0.notify();
+    }
 }
 
 impl Pipe {
     pub fn new() -> (Pipe, Pipe) {
         let buffer = Arc::new(Mutex::new(PipeRingBuffer::new()));
+        let waiters = Arc::new(PollWaiters::new());
         let read_end = Pipe {
             readable: true,
             buffer: buffer.clone(),
+            waiters: PipeWaiters(waiters.clone()),
         };
         let write_end = Pipe {
             readable: false,
             buffer,
+            waiters: PipeWaiters(waiters),
         };
         (read_end, write_end)
     }
@@ -104,10 +123,8 @@ impl Pipe {
     pub fn write_end_close(&self) -> bool {
         Arc::strong_count(&self.buffer) == 1
     }
-}
 
-impl FileLike for Pipe {
-    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+    fn read_blocked(&self, buf: &mut [u8]) -> LinuxResult<usize> {
         if !self.readable() {
             return Err(LinuxError::EPERM);
         }
@@ -117,10 +134,14 @@ impl FileLike for Pipe {
             let mut ring_buffer = self.buffer.lock();
             let loop_read = ring_buffer.available_read();
             if loop_read == 0 {
-                if self.write_end_close() {
+                // Return what has been read instead of waiting for more.
+                if read_size > 0 || self.write_end_close() {
                     return Ok(read_size);
                 }
                 drop(ring_buffer);
+                if super::task::interrupted() {
+                    return Err(LinuxError::EINTR);
+                }
                 // Data not ready, wait for write end
                 crate::sys_sched_yield(); // TODO: use synconize primitive
                 continue;
@@ -135,7 +156,7 @@ impl FileLike for Pipe {
         }
     }
 
-    fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
+    fn write_blocked(&self, buf: &[u8]) -> LinuxResult<usize> {
         if !self.writable() {
             return Err(LinuxError::EPERM);
         }
@@ -146,7 +167,17 @@ impl FileLike for Pipe {
             let loop_write = ring_buffer.available_write();
             if loop_write == 0 {
                 drop(ring_buffer);
-                // Buffer is full, wait for read end to consume
+                // Return what has been written instead of waiting for room.
+                if super::task::interrupted() {
+                    return if write_size > 0 {
+                        Ok(write_size)
+                    } else {
+                        Err(LinuxError::EINTR)
+                    };
+                }
+                // Buffer is full, wait for read end to consume. Its pollers
+                // need to see the data written so far.
+                self.waiters.0.notify();
                 crate::sys_sched_yield(); // TODO: use synconize primitive
                 continue;
             }
@@ -159,6 +190,20 @@ impl FileLike for Pipe {
             }
         }
     }
+}
+
+impl FileLike for Pipe {
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        let res = self.read_blocked(buf);
+        self.waiters.0.notify();
+        res
+    }
+
+    fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
+        let res = self.write_blocked(buf);
+        self.waiters.0.notify();
+        res
+    }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
         let st_mode = 0o10000 | 0o600u32; // S_IFIFO | rw-------
@@ -179,15 +224,31 @@ impl FileLike for Pipe {
 
     fn poll(&self) -> LinuxResult<PollState> {
         let buf = self.buffer.lock();
+        // Reading or writing doesn't block either if the other end is closed.
+        let closed = self.write_end_close();
         Ok(PollState {
-            readable: self.readable() && buf.available_read() > 0,
-            writable: self.writable() && buf.available_write() > 0,
+            readable: self.readable() && (buf.available_read() > 0 || closed),
+            writable: self.writable() && (buf.available_write() > 0 || closed),
         })
     }
 
     fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
         Ok(())
     }
+
+    fn poll_conditions(&self) -> PollConditions {
+        // A closed write end hangs up the read end, and a closed read end is
+        // an error for the write end, as on Linux.
+        let closed = self.write_end_close();
+        PollConditions {
+            hangup: self.readable() && closed,
+            error: self.writable() && closed,
+        }
+    }
+
+    fn poll_waiters(&self) -> Option<&PollWaiters> {
+        Some(&self.waiters.0)
+    }
 }
 
 /// Create a pipe
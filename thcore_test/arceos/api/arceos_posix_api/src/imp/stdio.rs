@@ -1,9 +1,31 @@
-use axerrno::AxResult;
+use axerrno::{AxResult, LinuxError, LinuxResult};
 use axio::{BufReader, prelude::*};
 use axsync::Mutex;
 
 #[cfg(feature = "fd")]
-use {alloc::sync::Arc, axerrno::LinuxError, axerrno::LinuxResult, axio::PollState};
+use {alloc::sync::Arc, axio::PollState};
+
+/// The handler of `ioctl` requests on the console, see [`set_console_ioctl`].
+#[cfg(feature = "fd")]
+static CONSOLE_IOCTL: spin::Once<fn(u32, usize) -> LinuxResult<usize>> = spin::Once::new();
+
+/// Set the handler of `ioctl` requests on the console, i.e., stdin, stdout
+/// and stderr, which makes the console a terminal.
+///
+/// Without a handler, the console is not a terminal and every request fails
+/// with `ENOTTY`.
+#[cfg(feature = "fd")]
+pub fn set_console_ioctl(handler: fn(u32, usize) -> LinuxResult<usize>) {
+    CONSOLE_IOCTL.call_once(|| handler);
+}
+
+#[cfg(feature = "fd")]
+fn console_ioctl(cmd: u32, arg: usize) -> LinuxResult<usize> {
+    match CONSOLE_IOCTL.get() {
+        Some(handler) => handler(cmd, arg),
+        None => Err(LinuxError::ENOTTY),
+    }
+}
 
 fn console_read_bytes(buf: &mut [u8]) -> AxResult<usize> {
     let len = axhal::console::read_bytes(buf);
@@ -53,8 +75,9 @@ pub struct Stdin {
 }
 
 impl Stdin {
-    // Block until at least one byte is read.
-    fn read_blocked(&self, buf: &mut [u8]) -> AxResult<usize> {
+    // Block until at least one byte is read, or the current task is
+    // interrupted.
+    pub(crate) fn read_blocked(&self, buf: &mut [u8]) -> LinuxResult<usize> {
         let read_len = self.inner.lock().read(buf)?;
         if buf.is_empty() || read_len > 0 {
             return Ok(read_len);
@@ -65,17 +88,14 @@ impl Stdin {
             if read_len > 0 {
                 return Ok(read_len);
             }
+            if super::task::interrupted() {
+                return Err(LinuxError::EINTR);
+            }
             crate::sys_sched_yield();
         }
     }
 }
 
-impl Read for Stdin {
-    fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        self.read_blocked(buf)
-    }
-}
-
 pub struct Stdout {
     inner: &'static Mutex<StdoutRaw>,
 }
@@ -105,7 +125,7 @@ pub fn stdout() -> Stdout {
 #[cfg(feature = "fd")]
 impl super::fd_ops::FileLike for Stdin {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.read_blocked(buf)?)
+        self.read_blocked(buf)
     }
 
     fn write(&self, _buf: &[u8]) -> LinuxResult<usize> {
@@ -127,15 +147,20 @@ impl super::fd_ops::FileLike for Stdin {
     }
 
     fn poll(&self) -> LinuxResult<PollState> {
+        // Readable only if some input is buffered or can be read right away.
         Ok(PollState {
-            readable: true,
-            writable: true,
+            readable: !self.inner.lock().fill_buf()?.is_empty(),
+            writable: false,
         })
     }
 
     fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
         Ok(())
     }
+
+    fn ioctl(&self, cmd: u32, arg: usize) -> LinuxResult<usize> {
+        console_ioctl(cmd, arg)
+    }
 }
 
 #[cfg(feature = "fd")]
@@ -164,7 +189,7 @@ impl super::fd_ops::FileLike for Stdout {
 
     fn poll(&self) -> LinuxResult<PollState> {
         Ok(PollState {
-            readable: true,
+            readable: false,
             writable: true,
         })
     }
@@ -172,4 +197,8 @@ impl super::fd_ops::FileLike for Stdout {
     fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
         Ok(())
     }
+
+    fn ioctl(&self, cmd: u32, arg: usize) -> LinuxResult<usize> {
+        console_ioctl(cmd, arg)
+    }
 }
@@ -16,6 +16,21 @@ pub fn sys_sched_yield() -> c_int {
     0
 }
 
+/// Whether the current task has been interrupted, so a blocking call should
+/// give up with `EINTR`.
+///
+/// It's never interrupted in the single-threaded configuration.
+pub(crate) fn interrupted() -> bool {
+    #[cfg(feature = "multitask")]
+    {
+        axtask::current().is_interrupted()
+    }
+    #[cfg(not(feature = "multitask"))]
+    {
+        false
+    }
+}
+
 /// Get current thread ID.
 pub fn sys_getpid() -> c_int {
     syscall_body!(sys_getpid,
@@ -5,6 +5,9 @@
 #![cfg_attr(all(not(test), not(doc)), no_std)]
 #![feature(doc_cfg)]
 #![feature(doc_auto_cfg)]
+// For the bitmap of `FlattenObjects`, sized from its capacity.
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
 #![allow(clippy::missing_safety_doc)]
 
 #[macro_use]
@@ -39,9 +42,12 @@ pub use imp::sys::sys_sysconf;
 pub use imp::task::{sys_exit, sys_getpid, sys_sched_yield};
 pub use imp::time::{sys_clock_gettime, sys_get_time_of_day, sys_nanosleep};
 
+#[cfg(all(feature = "fd", feature = "multitask"))]
+pub use imp::fd_ops::Poller;
 #[cfg(feature = "fd")]
 pub use imp::fd_ops::{
-    FD_TABLE, add_file_like, get_file_like, sys_close, sys_dup, sys_dup2, sys_fcntl,
+    AX_FILE_LIMIT, FD_LIMIT, FD_TABLE, FileLike, PollConditions, PollWaiters, add_file_like,
+    get_file_like, sys_close, sys_dup, sys_dup2, sys_fcntl,
 };
 #[cfg(feature = "fs")]
 pub use imp::fs::{
@@ -66,3 +72,5 @@ pub use imp::pthread::mutex::{
 };
 #[cfg(feature = "multitask")]
 pub use imp::pthread::{sys_pthread_create, sys_pthread_exit, sys_pthread_join, sys_pthread_self};
+#[cfg(feature = "fd")]
+pub use imp::stdio::set_console_ioctl;
@@ -45,6 +45,67 @@ impl TrapFrame {
     pub const fn arg5(&self) -> usize {
         self.r[5] as _
     }
+
+    /// Sets the 0th syscall argument.
+    pub const fn set_arg0(&mut self, a0: usize) {
+        self.r[0] = a0 as _;
+    }
+
+    /// Sets the 1st syscall argument.
+    pub const fn set_arg1(&mut self, a1: usize) {
+        self.r[1] = a1 as _;
+    }
+
+    /// Sets the 2nd syscall argument.
+    pub const fn set_arg2(&mut self, a2: usize) {
+        self.r[2] = a2 as _;
+    }
+
+    /// Sets the 3rd syscall argument.
+    pub const fn set_arg3(&mut self, a3: usize) {
+        self.r[3] = a3 as _;
+    }
+
+    /// Sets the 4th syscall argument.
+    pub const fn set_arg4(&mut self, a4: usize) {
+        self.r[4] = a4 as _;
+    }
+
+    /// Sets the 5th syscall argument.
+    pub const fn set_arg5(&mut self, a5: usize) {
+        self.r[5] = a5 as _;
+    }
+
+    /// Gets the syscall number (`x8`).
+    pub const fn syscall_num(&self) -> usize {
+        self.r[8] as _
+    }
+
+    /// Sets the return value register (`x0`), which is also the 0th syscall
+    /// argument.
+    pub const fn set_ret(&mut self, ret: usize) {
+        self.r[0] = ret as _;
+    }
+
+    /// Gets the instruction pointer, i.e. where the trap returns to.
+    pub const fn ip(&self) -> usize {
+        self.elr as _
+    }
+
+    /// Sets the instruction pointer.
+    pub const fn set_ip(&mut self, pc: usize) {
+        self.elr = pc as _;
+    }
+
+    /// Gets the stack pointer (`SP_EL0`).
+    pub const fn sp(&self) -> usize {
+        self.usp as _
+    }
+
+    /// Sets the stack pointer.
+    pub const fn set_sp(&mut self, sp: usize) {
+        self.usp = sp as _;
+    }
 }
 
 /// Context to enter user space.
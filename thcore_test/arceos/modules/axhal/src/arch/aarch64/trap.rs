@@ -5,6 +5,8 @@ use page_table_entry::MappingFlags;
 use tock_registers::interfaces::Readable;
 
 use super::TrapFrame;
+#[cfg(feature = "uspace")]
+use crate::trap::ExceptionKind;
 
 global_asm!(include_str!("trap.S"), cache_current_task_ptr = sym crate::cpu::cache_current_task_ptr);
 
@@ -36,9 +38,24 @@ fn invalid_exception(tf: &TrapFrame, kind: TrapKind, source: TrapSource) {
     );
 }
 
+/// Whether the trap is taken from EL0, i.e., the saved `SPSR_EL1.M` is `EL0t`.
+#[cfg(feature = "uspace")]
+fn is_from_user(tf: &TrapFrame) -> bool {
+    tf.spsr & 0b1111 == 0
+}
+
 #[unsafe(no_mangle)]
-fn handle_irq_exception(_tf: &TrapFrame) {
+#[cfg_attr(not(feature = "uspace"), allow(unused_variables))]
+fn handle_irq_exception(tf: &TrapFrame) {
+    #[cfg(feature = "uspace")]
+    if is_from_user(tf) {
+        crate::trap::handle_user_entry();
+    }
     handle_trap!(IRQ, 0);
+    #[cfg(feature = "uspace")]
+    if is_from_user(tf) {
+        crate::trap::handle_user_return();
+    }
 }
 
 fn handle_instruction_abort(tf: &TrapFrame, iss: u64, is_user: bool) {
@@ -93,19 +110,46 @@ fn handle_data_abort(tf: &TrapFrame, iss: u64, is_user: bool) {
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame, kind: ExceptionKind, vaddr: usize) {
+    debug!("User {:?} @ {:#x}, fault_vaddr={:#x}", kind, tf.elr, vaddr);
+    if !handle_trap!(EXCEPTION, kind, va!(tf.elr as usize), va!(vaddr)) {
+        panic!(
+            "Unhandled user {:?} @ {:#x}, fault_vaddr={:#x}:\n{:#x?}",
+            kind, tf.elr, vaddr, tf
+        );
+    }
+}
+
 #[unsafe(no_mangle)]
 fn handle_sync_exception(tf: &mut TrapFrame) {
+    #[cfg(feature = "uspace")]
+    if is_from_user(tf) {
+        crate::trap::handle_user_entry();
+    }
     let esr = ESR_EL1.extract();
     let iss = esr.read(ESR_EL1::ISS);
     match esr.read_as_enum(ESR_EL1::EC) {
         #[cfg(feature = "uspace")]
         Some(ESR_EL1::EC::Value::SVC64) => {
-            tf.r[0] = crate::trap::handle_syscall(tf, tf.r[8] as usize) as u64;
+            let syscall_num = tf.r[8] as usize;
+            tf.r[0] = crate::trap::handle_syscall(tf, syscall_num) as u64;
         }
         Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => handle_instruction_abort(tf, iss, true),
         Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => handle_instruction_abort(tf, iss, false),
         Some(ESR_EL1::EC::Value::DataAbortLowerEL) => handle_data_abort(tf, iss, true),
         Some(ESR_EL1::EC::Value::DataAbortCurrentEL) => handle_data_abort(tf, iss, false),
+        // An undefined instruction is reported with an unknown reason.
+        #[cfg(feature = "uspace")]
+        Some(ESR_EL1::EC::Value::Unknown) if is_from_user(tf) => {
+            handle_user_exception(tf, ExceptionKind::IllegalInstruction, 0)
+        }
+        #[cfg(feature = "uspace")]
+        Some(ESR_EL1::EC::Value::PCAlignmentFault | ESR_EL1::EC::Value::SPAlignmentFault)
+            if is_from_user(tf) =>
+        {
+            handle_user_exception(tf, ExceptionKind::Misaligned, FAR_EL1.get() as usize)
+        }
         Some(ESR_EL1::EC::Value::Brk64) => {
             debug!("BRK #{:#x} @ {:#x} ", iss, tf.elr);
             tf.elr += 4;
@@ -120,4 +164,8 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
             );
         }
     }
+    #[cfg(feature = "uspace")]
+    if is_from_user(tf) {
+        crate::trap::handle_user_return();
+    }
 }
@@ -46,6 +46,77 @@ impl TrapFrame {
     pub const fn arg5(&self) -> usize {
         self.regs[9] as _
     }
+
+    /// Sets the 0th syscall argument.
+    pub const fn set_arg0(&mut self, a0: usize) {
+        self.regs[4] = a0;
+    }
+
+    /// Sets the 1st syscall argument.
+    pub const fn set_arg1(&mut self, a1: usize) {
+        self.regs[5] = a1;
+    }
+
+    /// Sets the 2nd syscall argument.
+    pub const fn set_arg2(&mut self, a2: usize) {
+        self.regs[6] = a2;
+    }
+
+    /// Sets the 3rd syscall argument.
+    pub const fn set_arg3(&mut self, a3: usize) {
+        self.regs[7] = a3;
+    }
+
+    /// Sets the 4th syscall argument.
+    pub const fn set_arg4(&mut self, a4: usize) {
+        self.regs[8] = a4;
+    }
+
+    /// Sets the 5th syscall argument.
+    pub const fn set_arg5(&mut self, a5: usize) {
+        self.regs[9] = a5;
+    }
+
+    /// Gets the syscall number (`$a7`).
+    pub const fn syscall_num(&self) -> usize {
+        self.regs[11]
+    }
+
+    /// Sets the return value register (`$a0`), which is also the 0th syscall
+    /// argument.
+    pub const fn set_ret(&mut self, ret: usize) {
+        self.regs[4] = ret;
+    }
+
+    /// Gets the instruction pointer, i.e. where the trap returns to.
+    pub const fn ip(&self) -> usize {
+        self.era
+    }
+
+    /// Sets the instruction pointer.
+    pub const fn set_ip(&mut self, pc: usize) {
+        self.era = pc;
+    }
+
+    /// Gets the stack pointer (`$sp`).
+    pub const fn sp(&self) -> usize {
+        self.regs[3]
+    }
+
+    /// Sets the stack pointer.
+    pub const fn set_sp(&mut self, sp: usize) {
+        self.regs[3] = sp;
+    }
+
+    /// Gets the thread pointer (`$tp`).
+    pub const fn tls(&self) -> usize {
+        self.regs[2]
+    }
+
+    /// Sets the thread pointer.
+    pub const fn set_tls(&mut self, tp: usize) {
+        self.regs[2] = tp;
+    }
 }
 
 /// Context to enter user space.
@@ -65,10 +136,10 @@ impl UspaceContext {
         let mut trap_frame = TrapFrame::default();
         const PPLV_UMODE: usize = 0b11;
         const PIE: usize = 1 << 2;
-        trap_frame.regs[3] = ustack_top.as_usize();
-        trap_frame.era = entry;
+        trap_frame.set_sp(ustack_top.as_usize());
+        trap_frame.set_ip(entry);
         trap_frame.prmd = PPLV_UMODE | PIE;
-        trap_frame.regs[4] = arg0;
+        trap_frame.set_arg0(arg0);
         Self(trap_frame)
     }
 
@@ -79,27 +150,27 @@ impl UspaceContext {
 
     /// Gets the instruction pointer.
     pub const fn get_ip(&self) -> usize {
-        self.0.era
+        self.0.ip()
     }
 
     /// Gets the stack pointer.
     pub const fn get_sp(&self) -> usize {
-        self.0.regs[3]
+        self.0.sp()
     }
 
     /// Sets the instruction pointer.
     pub const fn set_ip(&mut self, pc: usize) {
-        self.0.era = pc;
+        self.0.set_ip(pc);
     }
 
     /// Sets the stack pointer.
     pub const fn set_sp(&mut self, sp: usize) {
-        self.0.regs[3] = sp;
+        self.0.set_sp(sp);
     }
 
     /// Sets the return value register.
     pub const fn set_retval(&mut self, a0: usize) {
-        self.0.regs[4] = a0;
+        self.0.set_ret(a0);
     }
 
     /// Enters user space.
@@ -114,9 +185,11 @@ impl UspaceContext {
     /// This function is unsafe because it changes processor mode and the stack.
     #[unsafe(no_mangle)]
     pub unsafe fn enter_uspace(&self, kstack_top: VirtAddr) -> ! {
-        use loongArch64::register::{CpuMode, era, prmd};
+        use loongArch64::register::{CpuMode, era, euen, prmd};
 
         super::disable_irqs();
+        // User programs use the FPU, whose state is switched with the task.
+        euen::set_fpe(true);
         prmd::set_pplv(CpuMode::Ring3);
         prmd::set_pie(true);
         era::set_pc(self.get_ip());
@@ -179,6 +252,34 @@ impl UspaceContext {
     }
 }
 
+/// Floating-point registers.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct FpState {
+    /// Floating-point registers ($f0..$f31)
+    pub fp: [u64; 32],
+    /// Floating-point condition flags ($fcc0..$fcc7)
+    pub fcc: [u8; 8],
+    /// Floating-point Control and Status Register ($fcsr0)
+    pub fcsr: u32,
+}
+
+#[cfg(feature = "fp_simd")]
+impl FpState {
+    /// Saves the floating-point registers of the CPU to this place.
+    ///
+    /// E.g., a task created by `fork` or `clone` starts with the registers of
+    /// its parent saved by it.
+    pub fn save(&mut self) {
+        unsafe { fpstate_save(self) }
+    }
+
+    fn switch_to(&mut self, next_fpstate: &FpState) {
+        self.save();
+        unsafe { fpstate_restore(next_fpstate) }
+    }
+}
+
 /// Saved hardware states of a task.
 ///
 /// The context usually includes:
@@ -200,6 +301,8 @@ pub struct TaskContext {
     pub tp: usize,
     #[cfg(feature = "uspace")]
     pub pgdl: usize,
+    #[cfg(feature = "fp_simd")]
+    pub fp_state: FpState,
 }
 
 impl TaskContext {
@@ -232,6 +335,8 @@ impl TaskContext {
     /// It first saves the current task's context from CPU to this place, and then
     /// restores the next task's context from `next_ctx` to CPU.
     pub fn switch_to(&mut self, next_ctx: &Self) {
+        #[cfg(feature = "fp_simd")]
+        self.fp_state.switch_to(&next_ctx.fp_state);
         #[cfg(feature = "tls")]
         {
             self.tp = super::read_thread_pointer();
@@ -284,3 +389,129 @@ unsafe extern "C" fn context_switch(_current_task: &mut TaskContext, _next_task:
         )
     }
 }
+
+#[naked]
+#[cfg(feature = "fp_simd")]
+unsafe extern "C" fn fpstate_save(_fpstate: &mut FpState) {
+    unsafe {
+        naked_asm!(
+            "
+            // save fp context
+            fst.d    $f0, $a0, 0 * 8
+            fst.d    $f1, $a0, 1 * 8
+            fst.d    $f2, $a0, 2 * 8
+            fst.d    $f3, $a0, 3 * 8
+            fst.d    $f4, $a0, 4 * 8
+            fst.d    $f5, $a0, 5 * 8
+            fst.d    $f6, $a0, 6 * 8
+            fst.d    $f7, $a0, 7 * 8
+            fst.d    $f8, $a0, 8 * 8
+            fst.d    $f9, $a0, 9 * 8
+            fst.d    $f10, $a0, 10 * 8
+            fst.d    $f11, $a0, 11 * 8
+            fst.d    $f12, $a0, 12 * 8
+            fst.d    $f13, $a0, 13 * 8
+            fst.d    $f14, $a0, 14 * 8
+            fst.d    $f15, $a0, 15 * 8
+            fst.d    $f16, $a0, 16 * 8
+            fst.d    $f17, $a0, 17 * 8
+            fst.d    $f18, $a0, 18 * 8
+            fst.d    $f19, $a0, 19 * 8
+            fst.d    $f20, $a0, 20 * 8
+            fst.d    $f21, $a0, 21 * 8
+            fst.d    $f22, $a0, 22 * 8
+            fst.d    $f23, $a0, 23 * 8
+            fst.d    $f24, $a0, 24 * 8
+            fst.d    $f25, $a0, 25 * 8
+            fst.d    $f26, $a0, 26 * 8
+            fst.d    $f27, $a0, 27 * 8
+            fst.d    $f28, $a0, 28 * 8
+            fst.d    $f29, $a0, 29 * 8
+            fst.d    $f30, $a0, 30 * 8
+            fst.d    $f31, $a0, 31 * 8
+            movcf2gr $t0, $fcc0
+            st.b     $t0, $a0, 32 * 8 + 0
+            movcf2gr $t0, $fcc1
+            st.b     $t0, $a0, 32 * 8 + 1
+            movcf2gr $t0, $fcc2
+            st.b     $t0, $a0, 32 * 8 + 2
+            movcf2gr $t0, $fcc3
+            st.b     $t0, $a0, 32 * 8 + 3
+            movcf2gr $t0, $fcc4
+            st.b     $t0, $a0, 32 * 8 + 4
+            movcf2gr $t0, $fcc5
+            st.b     $t0, $a0, 32 * 8 + 5
+            movcf2gr $t0, $fcc6
+            st.b     $t0, $a0, 32 * 8 + 6
+            movcf2gr $t0, $fcc7
+            st.b     $t0, $a0, 32 * 8 + 7
+            movfcsr2gr $t0, $fcsr0
+            st.w     $t0, $a0, 33 * 8
+
+            ret",
+        )
+    }
+}
+
+#[naked]
+#[cfg(feature = "fp_simd")]
+unsafe extern "C" fn fpstate_restore(_fpstate: &FpState) {
+    unsafe {
+        naked_asm!(
+            "
+            // restore fp context
+            fld.d    $f0, $a0, 0 * 8
+            fld.d    $f1, $a0, 1 * 8
+            fld.d    $f2, $a0, 2 * 8
+            fld.d    $f3, $a0, 3 * 8
+            fld.d    $f4, $a0, 4 * 8
+            fld.d    $f5, $a0, 5 * 8
+            fld.d    $f6, $a0, 6 * 8
+            fld.d    $f7, $a0, 7 * 8
+            fld.d    $f8, $a0, 8 * 8
+            fld.d    $f9, $a0, 9 * 8
+            fld.d    $f10, $a0, 10 * 8
+            fld.d    $f11, $a0, 11 * 8
+            fld.d    $f12, $a0, 12 * 8
+            fld.d    $f13, $a0, 13 * 8
+            fld.d    $f14, $a0, 14 * 8
+            fld.d    $f15, $a0, 15 * 8
+            fld.d    $f16, $a0, 16 * 8
+            fld.d    $f17, $a0, 17 * 8
+            fld.d    $f18, $a0, 18 * 8
+            fld.d    $f19, $a0, 19 * 8
+            fld.d    $f20, $a0, 20 * 8
+            fld.d    $f21, $a0, 21 * 8
+            fld.d    $f22, $a0, 22 * 8
+            fld.d    $f23, $a0, 23 * 8
+            fld.d    $f24, $a0, 24 * 8
+            fld.d    $f25, $a0, 25 * 8
+            fld.d    $f26, $a0, 26 * 8
+            fld.d    $f27, $a0, 27 * 8
+            fld.d    $f28, $a0, 28 * 8
+            fld.d    $f29, $a0, 29 * 8
+            fld.d    $f30, $a0, 30 * 8
+            fld.d    $f31, $a0, 31 * 8
+            ld.b     $t0, $a0, 32 * 8 + 0
+            movgr2cf $fcc0, $t0
+            ld.b     $t0, $a0, 32 * 8 + 1
+            movgr2cf $fcc1, $t0
+            ld.b     $t0, $a0, 32 * 8 + 2
+            movgr2cf $fcc2, $t0
+            ld.b     $t0, $a0, 32 * 8 + 3
+            movgr2cf $fcc3, $t0
+            ld.b     $t0, $a0, 32 * 8 + 4
+            movgr2cf $fcc4, $t0
+            ld.b     $t0, $a0, 32 * 8 + 5
+            movgr2cf $fcc5, $t0
+            ld.b     $t0, $a0, 32 * 8 + 6
+            movgr2cf $fcc6, $t0
+            ld.b     $t0, $a0, 32 * 8 + 7
+            movgr2cf $fcc7, $t0
+            ld.w     $t0, $a0, 33 * 8
+            movgr2fcsr $fcsr0, $t0
+
+            ret",
+        )
+    }
+}
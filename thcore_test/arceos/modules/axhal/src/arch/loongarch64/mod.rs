@@ -112,6 +112,15 @@ pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     }
 }
 
+/// Flushes the entire instruction cache of the current CPU.
+///
+/// It's needed after writing instructions to memory, e.g. loading a program.
+#[inline]
+pub fn flush_icache_all() {
+    // <https://loongson.github.io/LoongArch-Documentation/LoongArch-Vol1-EN.html#_ibar>
+    unsafe { asm!("ibar 0") }
+}
+
 /// Writes Exception Entry Base Address Register (`eentry`).
 ///
 /// - ecfg: <https://loongson.github.io/LoongArch-Documentation/LoongArch-Vol1-EN.html#exception-configuration>
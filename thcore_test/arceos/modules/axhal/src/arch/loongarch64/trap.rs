@@ -5,6 +5,9 @@ use loongArch64::register::{
 };
 use page_table_entry::MappingFlags;
 
+#[cfg(feature = "uspace")]
+use crate::trap::ExceptionKind;
+
 core::arch::global_asm!(
     include_str!("trap.S"),
     trapframe_size = const (core::mem::size_of::<TrapFrame>()),
@@ -32,14 +35,30 @@ fn handle_page_fault(tf: &TrapFrame, mut access_flags: MappingFlags, is_user: bo
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame, kind: ExceptionKind) {
+    debug!("User {:?} @ {:#x}, badv={:#x}", kind, tf.era, tf.badv);
+    if !handle_trap!(EXCEPTION, kind, va!(tf.era), va!(tf.badv)) {
+        panic!(
+            "Unhandled user {:?} @ {:#x}, badv={:#x}:\n{:#x?}",
+            kind, tf.era, tf.badv, tf
+        );
+    }
+}
+
 #[unsafe(no_mangle)]
 fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
+    #[cfg(feature = "uspace")]
+    if from_user {
+        crate::trap::handle_user_entry();
+    }
     let estat = estat::read();
 
     match estat.cause() {
         #[cfg(feature = "uspace")]
         Trap::Exception(Exception::Syscall) => {
-            tf.regs[4] = crate::trap::handle_syscall(tf, tf.regs[11]) as usize;
+            let syscall_num = tf.regs[11];
+            tf.regs[4] = crate::trap::handle_syscall(tf, syscall_num) as usize;
             tf.era += 4;
         }
         Trap::Exception(Exception::LoadPageFault) | Trap::Exception(Exception::FetchPageFault) => {
@@ -49,6 +68,20 @@ fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
             handle_page_fault(tf, MappingFlags::WRITE, from_user)
         }
         Trap::Exception(Exception::Breakpoint) => handle_breakpoint(&mut tf.era),
+        #[cfg(feature = "uspace")]
+        Trap::Exception(
+            Exception::InstructionNotExist | Exception::InstructionPrivilegeIllegal,
+        ) if from_user => handle_user_exception(tf, ExceptionKind::IllegalInstruction),
+        #[cfg(feature = "uspace")]
+        Trap::Exception(Exception::AddressNotAligned) if from_user => {
+            handle_user_exception(tf, ExceptionKind::Misaligned)
+        }
+        #[cfg(feature = "uspace")]
+        Trap::Exception(
+            Exception::FetchInstructionAddressError
+            | Exception::MemoryAccessAddressError
+            | Exception::BoundsCheckFault,
+        ) if from_user => handle_user_exception(tf, ExceptionKind::BadAddress),
         Trap::Interrupt(_) => {
             let irq_num: usize = estat.is().trailing_zeros() as usize;
             handle_trap!(IRQ, irq_num);
@@ -62,4 +95,8 @@ fn loongarch64_trap_handler(tf: &mut TrapFrame, from_user: bool) {
             );
         }
     }
+    #[cfg(feature = "uspace")]
+    if from_user {
+        crate::trap::handle_user_return();
+    }
 }
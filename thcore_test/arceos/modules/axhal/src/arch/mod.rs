@@ -15,3 +15,43 @@ cfg_if::cfg_if! {
         pub use self::loongarch64::*;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TrapFrame;
+
+    #[test]
+    fn trap_frame_registers() {
+        let mut tf = TrapFrame::default();
+        tf.set_arg0(1);
+        tf.set_arg1(2);
+        tf.set_arg2(3);
+        tf.set_arg3(4);
+        tf.set_arg4(5);
+        tf.set_arg5(6);
+        assert_eq!(
+            [
+                tf.arg0(),
+                tf.arg1(),
+                tf.arg2(),
+                tf.arg3(),
+                tf.arg4(),
+                tf.arg5()
+            ],
+            [1, 2, 3, 4, 5, 6]
+        );
+
+        // The child of `clone` returns 0 with its own stack.
+        tf.set_ret(0);
+        tf.set_sp(0x4000_0000);
+        tf.set_ip(0x10_0000);
+        // The return value shares a register with the syscall number on
+        // x86_64, and with the 0th argument elsewhere.
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(tf.syscall_num(), 0);
+        #[cfg(not(target_arch = "x86_64"))]
+        assert_eq!(tf.arg0(), 0);
+        assert_eq!(tf.sp(), 0x4000_0000);
+        assert_eq!(tf.ip(), 0x10_0000);
+    }
+}
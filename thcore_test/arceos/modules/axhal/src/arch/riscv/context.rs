@@ -81,6 +81,77 @@ impl TrapFrame {
     pub const fn arg5(&self) -> usize {
         self.regs.a5
     }
+
+    /// Sets the 0th syscall argument.
+    pub const fn set_arg0(&mut self, a0: usize) {
+        self.regs.a0 = a0;
+    }
+
+    /// Sets the 1st syscall argument.
+    pub const fn set_arg1(&mut self, a1: usize) {
+        self.regs.a1 = a1;
+    }
+
+    /// Sets the 2nd syscall argument.
+    pub const fn set_arg2(&mut self, a2: usize) {
+        self.regs.a2 = a2;
+    }
+
+    /// Sets the 3rd syscall argument.
+    pub const fn set_arg3(&mut self, a3: usize) {
+        self.regs.a3 = a3;
+    }
+
+    /// Sets the 4th syscall argument.
+    pub const fn set_arg4(&mut self, a4: usize) {
+        self.regs.a4 = a4;
+    }
+
+    /// Sets the 5th syscall argument.
+    pub const fn set_arg5(&mut self, a5: usize) {
+        self.regs.a5 = a5;
+    }
+
+    /// Gets the syscall number (`a7`).
+    pub const fn syscall_num(&self) -> usize {
+        self.regs.a7
+    }
+
+    /// Sets the return value register (`a0`), which is also the 0th syscall
+    /// argument.
+    pub const fn set_ret(&mut self, ret: usize) {
+        self.regs.a0 = ret;
+    }
+
+    /// Gets the instruction pointer, i.e. where the trap returns to.
+    pub const fn ip(&self) -> usize {
+        self.sepc
+    }
+
+    /// Sets the instruction pointer.
+    pub const fn set_ip(&mut self, pc: usize) {
+        self.sepc = pc;
+    }
+
+    /// Gets the stack pointer (`sp`).
+    pub const fn sp(&self) -> usize {
+        self.regs.sp
+    }
+
+    /// Sets the stack pointer.
+    pub const fn set_sp(&mut self, sp: usize) {
+        self.regs.sp = sp;
+    }
+
+    /// Gets the thread pointer (`tp`).
+    pub const fn tls(&self) -> usize {
+        self.regs.tp
+    }
+
+    /// Sets the thread pointer.
+    pub const fn set_tls(&mut self, tp: usize) {
+        self.regs.tp = tp;
+    }
 }
 
 /// Context to enter user space.
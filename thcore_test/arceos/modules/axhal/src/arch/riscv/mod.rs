@@ -82,6 +82,14 @@ pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     }
 }
 
+/// Flushes the entire instruction cache of the current CPU.
+///
+/// It's needed after writing instructions to memory, e.g. loading a program.
+#[inline]
+pub fn flush_icache_all() {
+    asm::fence_i()
+}
+
 /// Writes Supervisor Trap Vector Base Address Register (`stvec`).
 #[inline]
 pub fn set_trap_vector_base(stvec: usize) {
@@ -4,6 +4,8 @@ use riscv::interrupt::supervisor::{Exception as E, Interrupt as I};
 use riscv::register::{scause, stval};
 
 use super::TrapFrame;
+#[cfg(feature = "uspace")]
+use crate::trap::ExceptionKind;
 
 core::arch::global_asm!(
     include_asm_macros!(),
@@ -33,14 +35,31 @@ fn handle_page_fault(tf: &TrapFrame, mut access_flags: MappingFlags, is_user: bo
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame, kind: ExceptionKind) {
+    let stval = stval::read();
+    debug!("User {:?} @ {:#x}, stval={:#x}", kind, tf.sepc, stval);
+    if !handle_trap!(EXCEPTION, kind, va!(tf.sepc), va!(stval)) {
+        panic!(
+            "Unhandled user {:?} @ {:#x}, stval={:#x}:\n{:#x?}",
+            kind, tf.sepc, stval, tf
+        );
+    }
+}
+
 #[unsafe(no_mangle)]
 fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
+    #[cfg(feature = "uspace")]
+    if from_user {
+        crate::trap::handle_user_entry();
+    }
     let scause = scause::read();
     if let Ok(cause) = scause.cause().try_into::<I, E>() {
         match cause {
             #[cfg(feature = "uspace")]
             Trap::Exception(E::UserEnvCall) => {
-                tf.regs.a0 = crate::trap::handle_syscall(tf, tf.regs.a7) as usize;
+                let syscall_num = tf.regs.a7;
+                tf.regs.a0 = crate::trap::handle_syscall(tf, syscall_num) as usize;
                 tf.sepc += 4;
             }
             Trap::Exception(E::LoadPageFault) => {
@@ -53,6 +72,20 @@ fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
                 handle_page_fault(tf, MappingFlags::EXECUTE, from_user)
             }
             Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::IllegalInstruction) if from_user => {
+                handle_user_exception(tf, ExceptionKind::IllegalInstruction)
+            }
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::InstructionMisaligned | E::LoadMisaligned | E::StoreMisaligned)
+                if from_user =>
+            {
+                handle_user_exception(tf, ExceptionKind::Misaligned)
+            }
+            #[cfg(feature = "uspace")]
+            Trap::Exception(E::InstructionFault | E::LoadFault | E::StoreFault) if from_user => {
+                handle_user_exception(tf, ExceptionKind::BadAddress)
+            }
             Trap::Interrupt(_) => {
                 handle_trap!(IRQ, scause.bits());
             }
@@ -68,4 +101,8 @@ fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
             tf
         );
     }
+    #[cfg(feature = "uspace")]
+    if from_user {
+        crate::trap::handle_user_return();
+    }
 }
@@ -64,6 +64,67 @@ impl TrapFrame {
         self.r9 as _
     }
 
+    /// Sets the 0th syscall argument.
+    pub const fn set_arg0(&mut self, a0: usize) {
+        self.rdi = a0 as _;
+    }
+
+    /// Sets the 1st syscall argument.
+    pub const fn set_arg1(&mut self, a1: usize) {
+        self.rsi = a1 as _;
+    }
+
+    /// Sets the 2nd syscall argument.
+    pub const fn set_arg2(&mut self, a2: usize) {
+        self.rdx = a2 as _;
+    }
+
+    /// Sets the 3rd syscall argument.
+    pub const fn set_arg3(&mut self, a3: usize) {
+        self.r10 = a3 as _;
+    }
+
+    /// Sets the 4th syscall argument.
+    pub const fn set_arg4(&mut self, a4: usize) {
+        self.r8 = a4 as _;
+    }
+
+    /// Sets the 5th syscall argument.
+    pub const fn set_arg5(&mut self, a5: usize) {
+        self.r9 = a5 as _;
+    }
+
+    /// Gets the syscall number (`rax`).
+    pub const fn syscall_num(&self) -> usize {
+        self.rax as _
+    }
+
+    /// Sets the return value register (`rax`), which is also the syscall
+    /// number.
+    pub const fn set_ret(&mut self, ret: usize) {
+        self.rax = ret as _;
+    }
+
+    /// Gets the instruction pointer, i.e. where the trap returns to.
+    pub const fn ip(&self) -> usize {
+        self.rip as _
+    }
+
+    /// Sets the instruction pointer.
+    pub const fn set_ip(&mut self, pc: usize) {
+        self.rip = pc as _;
+    }
+
+    /// Gets the stack pointer (`rsp`).
+    pub const fn sp(&self) -> usize {
+        self.rsp as _
+    }
+
+    /// Sets the stack pointer.
+    pub const fn set_sp(&mut self, sp: usize) {
+        self.rsp = sp as _;
+    }
+
     /// Whether the trap is from userspace.
     pub const fn is_user(&self) -> bool {
         self.cs & 0b11 == 3
@@ -102,6 +102,13 @@ pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     }
 }
 
+/// Flushes the entire instruction cache of the current CPU.
+///
+/// The instruction cache is coherent with the data cache on x86_64, so it's
+/// a no-op.
+#[inline]
+pub fn flush_icache_all() {}
+
 /// Reads the thread pointer of the current CPU.
 ///
 /// It is used to implement TLS (Thread Local Storage).
@@ -14,9 +14,17 @@ core::arch::global_asm!(
     tss_rsp0_offset = const core::mem::offset_of!(TaskStateSegment, privilege_stack_table),
 );
 
+/// Handles a syscall entered by either `syscall` or `int 0x80`.
+pub(super) fn handle_syscall(tf: &mut TrapFrame) {
+    let syscall_num = tf.rax as usize;
+    tf.rax = crate::trap::handle_syscall(tf, syscall_num) as u64;
+}
+
 #[unsafe(no_mangle)]
-pub(super) fn x86_syscall_handler(tf: &mut TrapFrame) {
-    tf.rax = crate::trap::handle_syscall(tf, tf.rax as usize) as u64;
+fn x86_syscall_handler(tf: &mut TrapFrame) {
+    crate::trap::handle_user_entry();
+    handle_syscall(tf);
+    crate::trap::handle_user_return();
 }
 
 /// Initializes syscall support and setups the syscall handler.
@@ -3,6 +3,8 @@ use x86::{controlregs::cr2, irq::*};
 use x86_64::structures::idt::PageFaultErrorCode;
 
 use super::context::TrapFrame;
+#[cfg(feature = "uspace")]
+use crate::trap::ExceptionKind;
 
 core::arch::global_asm!(include_str!("trap.S"));
 
@@ -29,11 +31,42 @@ fn handle_page_fault(tf: &TrapFrame) {
     }
 }
 
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame, kind: ExceptionKind) {
+    debug!(
+        "User {:?} @ {:#x}, error_code={:#x}",
+        kind, tf.rip, tf.error_code
+    );
+    // None of these exceptions reports a faulting address.
+    if !handle_trap!(EXCEPTION, kind, va!(tf.rip as usize), va!(0)) {
+        panic!(
+            "Unhandled user {:?} @ {:#x}, error_code={:#x}:\n{:#x?}",
+            kind, tf.rip, tf.error_code, tf
+        );
+    }
+}
+
 #[unsafe(no_mangle)]
 fn x86_trap_handler(tf: &mut TrapFrame) {
+    #[cfg(feature = "uspace")]
+    if tf.is_user() {
+        crate::trap::handle_user_entry();
+    }
     match tf.vector as u8 {
         PAGE_FAULT_VECTOR => handle_page_fault(tf),
         BREAKPOINT_VECTOR => debug!("#BP @ {:#x} ", tf.rip),
+        #[cfg(feature = "uspace")]
+        INVALID_OPCODE_VECTOR if tf.is_user() => {
+            handle_user_exception(tf, ExceptionKind::IllegalInstruction)
+        }
+        #[cfg(feature = "uspace")]
+        ALIGNMENT_CHECK_VECTOR if tf.is_user() => {
+            handle_user_exception(tf, ExceptionKind::Misaligned)
+        }
+        #[cfg(feature = "uspace")]
+        GENERAL_PROTECTION_FAULT_VECTOR if tf.is_user() => {
+            handle_user_exception(tf, ExceptionKind::BadAddress)
+        }
         GENERAL_PROTECTION_FAULT_VECTOR => {
             panic!(
                 "#GP @ {:#x}, error_code={:#x}:\n{:#x?}",
@@ -41,7 +74,7 @@ fn x86_trap_handler(tf: &mut TrapFrame) {
             );
         }
         #[cfg(feature = "uspace")]
-        LEGACY_SYSCALL_VECTOR => super::syscall::x86_syscall_handler(tf),
+        LEGACY_SYSCALL_VECTOR => super::syscall::handle_syscall(tf),
         IRQ_VECTOR_START..=IRQ_VECTOR_END => {
             handle_trap!(IRQ, tf.vector as _);
         }
@@ -56,6 +89,10 @@ fn x86_trap_handler(tf: &mut TrapFrame) {
             );
         }
     }
+    #[cfg(feature = "uspace")]
+    if tf.is_user() {
+        crate::trap::handle_user_return();
+    }
 }
 
 fn vec_to_str(vec: u64) -> &'static str {
@@ -1,16 +1,33 @@
 //! Interrupt management.
 
-use handler_table::HandlerTable;
+use handler_table::CountingHandlerTable;
 
 use crate::platform::irq::{MAX_IRQ_COUNT, dispatch_irq};
 use crate::trap::{IRQ, register_trap_handler};
 
-pub use crate::platform::irq::{register_handler, set_enable};
+pub use crate::platform::irq::{IPI_IRQ_NUM, register_handler, send_ipi, set_enable};
 
 /// The type if an IRQ handler.
-pub type IrqHandler = handler_table::Handler;
+///
+/// It receives the IRQ number, so a handler can be registered for several
+/// IRQs.
+pub type IrqHandler = handler_table::ArgHandler;
+
+static IRQ_HANDLER_TABLE: CountingHandlerTable<MAX_IRQ_COUNT> = CountingHandlerTable::new();
+
+/// Returns how many times the handler of the given IRQ was called, or 0 if the
+/// IRQ number is invalid.
+///
+/// The IRQs handled without the handler table (e.g. the timer IRQ on some
+/// platforms) are not counted.
+pub fn irq_count(irq_num: usize) -> usize {
+    IRQ_HANDLER_TABLE.invocation_count(irq_num)
+}
 
-static IRQ_HANDLER_TABLE: HandlerTable<MAX_IRQ_COUNT> = HandlerTable::new();
+/// Resets the count of every IRQ to zero.
+pub fn reset_irq_counts() {
+    IRQ_HANDLER_TABLE.reset_counts();
+}
 
 /// Platform-independent IRQ dispatching.
 #[allow(dead_code)]
@@ -47,6 +47,9 @@ pub mod cpu;
 pub mod mem;
 pub mod time;
 
+#[cfg(feature = "smp")]
+pub mod mp;
+
 #[cfg(feature = "tls")]
 pub mod tls;
 
@@ -64,12 +67,21 @@ pub mod console {
 /// Miscellaneous operation, e.g. terminate the system.
 pub mod misc {
     pub use super::platform::misc::*;
-}
 
-/// Multi-core operations.
-#[cfg(feature = "smp")]
-pub mod mp {
-    pub use super::platform::mp::*;
+    /// Shutdown the whole system, reporting a failure to the host if the
+    /// platform supports it, or the same as [`terminate`] otherwise.
+    ///
+    /// The platforms that can't report it include `loongarch64-qemu-virt`,
+    /// whose only way to shut down is the power-off of the GED device, so a
+    /// failure still exits QEMU with the status 0 there.
+    #[cfg(not(any(
+        all(target_arch = "riscv64", platform_family = "riscv64-qemu-virt"),
+        all(target_arch = "x86_64", platform_family = "x86-pc"),
+        all(target_arch = "aarch64", platform_family = "aarch64-qemu-virt"),
+    )))]
+    pub fn terminate_with_failure() -> ! {
+        terminate()
+    }
 }
 
 pub use self::platform::platform_init;
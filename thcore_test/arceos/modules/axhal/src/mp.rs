@@ -0,0 +1,73 @@
+//! Multi-core operations.
+
+pub use crate::platform::mp::*;
+
+#[cfg(feature = "irq")]
+pub use self::tlb::*;
+
+#[cfg(feature = "irq")]
+mod tlb {
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use axconfig::SMP;
+
+    use crate::cpu::{this_cpu_id, this_cpu_is_bsp};
+    use crate::irq::{IPI_IRQ_NUM, register_handler, send_ipi, set_enable};
+
+    /// The number of TLB shootdowns requested to each CPU.
+    static REQUESTED: [AtomicUsize; SMP] = [const { AtomicUsize::new(0) }; SMP];
+
+    /// The number of requested TLB shootdowns each CPU has done.
+    static DONE: [AtomicUsize; SMP] = [const { AtomicUsize::new(0) }; SMP];
+
+    /// Whether each CPU handles the TLB shootdown IPIs.
+    static READY: [AtomicBool; SMP] = [const { AtomicBool::new(false) }; SMP];
+
+    /// Enables the TLB shootdowns on the current CPU.
+    ///
+    /// The IPI handler is registered on the primary CPU, so it must be called
+    /// there before on the secondary CPUs.
+    pub fn init_tlb_shootdown() {
+        if this_cpu_is_bsp() {
+            register_handler(IPI_IRQ_NUM, |_| handle_tlb_shootdown());
+        }
+        set_enable(IPI_IRQ_NUM, true);
+        READY[this_cpu_id()].store(true, Ordering::Release);
+    }
+
+    /// Flushes the local TLB if other CPUs have requested it.
+    fn handle_tlb_shootdown() {
+        let cpu = this_cpu_id();
+        let requested = REQUESTED[cpu].load(Ordering::Acquire);
+        if DONE[cpu].load(Ordering::Relaxed) < requested {
+            crate::arch::flush_tlb(None);
+            DONE[cpu].fetch_max(requested, Ordering::Release);
+        }
+    }
+
+    /// Flushes the entire TLB on all CPUs, and waits until they are done.
+    ///
+    /// It's needed after the page table entries that other CPUs may have
+    /// cached are downgraded, e.g. write permissions are removed.
+    pub fn flush_tlb_all_cpus() {
+        let _guard = kernel_guard::NoPreempt::new();
+        let this_cpu = this_cpu_id();
+        crate::arch::flush_tlb(None);
+
+        let mut tickets = [0; SMP];
+        for (cpu, ticket) in tickets.iter_mut().enumerate() {
+            if cpu != this_cpu && READY[cpu].load(Ordering::Acquire) {
+                *ticket = REQUESTED[cpu].fetch_add(1, Ordering::AcqRel) + 1;
+                send_ipi(cpu);
+            }
+        }
+        for (cpu, &ticket) in tickets.iter().enumerate() {
+            while DONE[cpu].load(Ordering::Acquire) < ticket {
+                // Serve the requests to this CPU meanwhile, as the target may be
+                // waiting for us with IRQs disabled.
+                handle_tlb_shootdown();
+                core::hint::spin_loop();
+            }
+        }
+    }
+}
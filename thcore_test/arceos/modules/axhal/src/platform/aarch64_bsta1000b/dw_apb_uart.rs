@@ -61,6 +61,6 @@ pub fn init_irq() {
 }
 
 /// UART IRQ Handler
-pub fn handle() {
+pub fn handle(_irq_num: usize) {
     trace!("Uart IRQ Handler");
 }
@@ -10,6 +10,9 @@ pub const MAX_IRQ_COUNT: usize = 1024;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = translate_irq(14, InterruptType::PPI).unwrap();
 
+/// The inter-processor interrupt number, a software-generated interrupt.
+pub const IPI_IRQ_NUM: usize = translate_irq(1, InterruptType::SGI).unwrap();
+
 /// The UART IRQ number.
 pub const UART_IRQ_NUM: usize = translate_irq(UART_IRQ, InterruptType::SPI).unwrap();
 
@@ -46,6 +49,15 @@ pub fn dispatch_irq(_unused: usize) {
     GICC.handle_irq(|irq_num| crate::irq::dispatch_irq_common(irq_num as _));
 }
 
+/// Sends an inter-processor interrupt to the given CPU.
+pub fn send_ipi(cpu_id: usize) {
+    // The offset of `GICD_SGIR`, whose target list has a bit for each CPU
+    // interface from bit 16.
+    const GICD_SGIR: usize = 0xf00;
+    let sgir = phys_to_virt(GICD_BASE + GICD_SGIR).as_mut_ptr() as *mut u32;
+    unsafe { sgir.write_volatile((1 << (16 + cpu_id)) as u32 | IPI_IRQ_NUM as u32) };
+}
+
 /// Initializes GICD, GICC on the primary CPU.
 pub(crate) fn init_primary() {
     info!("Initialize GICv2...");
@@ -18,6 +18,27 @@ pub mod time {
 
 pub mod misc {
     pub use crate::platform::aarch64_common::psci::system_off as terminate;
+
+    /// Shutdown the whole system, reporting a failure to the host, e.g., QEMU
+    /// exits with a nonzero status.
+    ///
+    /// It asks QEMU to exit through semihosting, which has to be enabled with
+    /// `-semihosting`.
+    pub fn terminate_with_failure() -> ! {
+        info!("Shutting down on failure...");
+        // The parameter block of `SYS_EXIT`: `ADP_Stopped_ApplicationExit`
+        // and the exit status.
+        let block: [u64; 2] = [0x20026, 1];
+        unsafe {
+            core::arch::asm!(
+                "hlt #0xf000",
+                inout("x0") 0x18u64 => _,
+                in("x1") block.as_ptr(),
+                options(nostack)
+            )
+        };
+        terminate()
+    }
 }
 
 unsafe extern "C" {
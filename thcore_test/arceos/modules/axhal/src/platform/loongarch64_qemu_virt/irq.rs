@@ -1,5 +1,10 @@
 use crate::irq::IrqHandler;
 use lazyinit::LazyInit;
+use loongArch64::consts::{
+    LOONGARCH_IOCSR_IPI_CLEAR, LOONGARCH_IOCSR_IPI_EN, LOONGARCH_IOCSR_IPI_STATUS,
+};
+use loongArch64::iocsr::{iocsr_read_w, iocsr_write_w};
+use loongArch64::ipi::send_ipi_single;
 use loongArch64::register::{
     ecfg::{self, LineBasedInterrupt},
     ticlr,
@@ -14,12 +19,18 @@ pub const EXT_IRQ_NUM: usize = 2;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = 11;
 
+/// The inter-processor interrupt number.
+pub const IPI_IRQ_NUM: usize = 12;
+
 static TIMER_HANDLER: LazyInit<IrqHandler> = LazyInit::new();
 
+static IPI_HANDLER: LazyInit<IrqHandler> = LazyInit::new();
+
 macro_rules! with_cause {
-    ($cause: expr, @TIMER => $timer_op: expr, @EXT => $ext_op: expr $(,)?) => {
+    ($cause: expr, @TIMER => $timer_op: expr, @IPI => $ipi_op: expr, @EXT => $ext_op: expr $(,)?) => {
         match $cause {
             TIMER_IRQ_NUM => $timer_op,
+            IPI_IRQ_NUM => $ipi_op,
             EXT_IRQ_NUM => $ext_op,
             _ => panic!("invalid trap cause: {:#x}", $cause),
         }
@@ -28,14 +39,21 @@ macro_rules! with_cause {
 
 /// Enables or disables the given IRQ.
 pub fn set_enable(irq_num: usize, enabled: bool) {
-    if irq_num == TIMER_IRQ_NUM {
-        let old_value = ecfg::read().lie();
-        let new_value = match enabled {
-            true => old_value | LineBasedInterrupt::TIMER,
-            false => old_value & !LineBasedInterrupt::TIMER,
-        };
-        ecfg::set_lie(new_value);
-    }
+    let line = match irq_num {
+        TIMER_IRQ_NUM => LineBasedInterrupt::TIMER,
+        IPI_IRQ_NUM => {
+            // Accept all the IPI vectors, which are merged into one line.
+            iocsr_write_w(LOONGARCH_IOCSR_IPI_EN, if enabled { u32::MAX } else { 0 });
+            LineBasedInterrupt::IPI
+        }
+        _ => return,
+    };
+    let old_value = ecfg::read().lie();
+    let new_value = match enabled {
+        true => old_value | line,
+        false => old_value & !line,
+    };
+    ecfg::set_lie(new_value);
 }
 
 /// Registers an IRQ handler for the given IRQ.
@@ -49,6 +67,12 @@ pub fn register_handler(irq_num: usize, handler: crate::irq::IrqHandler) -> bool
         } else {
             false
         },
+        @IPI => if !IPI_HANDLER.is_inited() {
+            IPI_HANDLER.init_once(handler);
+            true
+        } else {
+            false
+        },
         @EXT => crate::irq::register_handler_common(irq_num, handler),
     )
 }
@@ -63,8 +87,17 @@ pub fn dispatch_irq(irq_num: usize) {
         irq_num,
         @TIMER => {
             ticlr::clear_timer_interrupt();
-            TIMER_HANDLER();
+            TIMER_HANDLER(TIMER_IRQ_NUM);
+        },
+        @IPI => {
+            iocsr_write_w(LOONGARCH_IOCSR_IPI_CLEAR, iocsr_read_w(LOONGARCH_IOCSR_IPI_STATUS));
+            IPI_HANDLER(IPI_IRQ_NUM);
         },
         @EXT => crate::irq::dispatch_irq_common(0),
     );
 }
+
+/// Sends an inter-processor interrupt to the given CPU.
+pub fn send_ipi(cpu_id: usize) {
+    send_ipi_single(cpu_id, 1);
+}
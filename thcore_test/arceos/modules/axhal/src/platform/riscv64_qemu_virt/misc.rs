@@ -7,3 +7,14 @@ pub fn terminate() -> ! {
         crate::arch::halt();
     }
 }
+
+/// Shutdown the whole system, reporting a failure to the host, e.g., QEMU
+/// exits with a nonzero status.
+pub fn terminate_with_failure() -> ! {
+    info!("Shutting down on failure...");
+    sbi_rt::system_reset(sbi_rt::Shutdown, sbi_rt::SystemFailure);
+    warn!("It should shutdown!");
+    loop {
+        crate::arch::halt();
+    }
+}
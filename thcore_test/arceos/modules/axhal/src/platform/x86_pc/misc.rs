@@ -26,3 +26,18 @@ pub fn terminate() -> ! {
         crate::arch::halt();
     }
 }
+
+/// Shutdown the whole system, reporting a failure to the host, e.g., QEMU
+/// exits with a nonzero status.
+///
+/// In QEMU, it writes to the `isa-debug-exit` device at the I/O port `0xf4`,
+/// which makes QEMU exit with the status `(1 << 1) | 1`. Without the device,
+/// it shuts down as [`terminate`] does.
+pub fn terminate_with_failure() -> ! {
+    info!("Shutting down on failure...");
+    #[cfg(platform = "x86_64-qemu-q35")]
+    unsafe {
+        PortWriteOnly::new(0xf4).write(1u32)
+    };
+    terminate()
+}
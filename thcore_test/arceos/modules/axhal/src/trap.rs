@@ -20,7 +20,42 @@ pub static PAGE_FAULT: [fn(VirtAddr, MappingFlags, bool) -> bool];
 /// A slice of syscall handler functions.
 #[cfg(feature = "uspace")]
 #[def_trap_handler]
-pub static SYSCALL: [fn(&TrapFrame, usize) -> isize];
+pub static SYSCALL: [fn(&mut TrapFrame, usize) -> isize];
+
+/// A slice of handler functions for the exceptions raised by user programs
+/// which the kernel can't resolve, e.g. an illegal instruction.
+///
+/// The arguments are the kind of the exception, the instruction pointer and
+/// the faulting address, if any. The handler usually terminates the current
+/// task; if there's none, the kernel panics.
+#[cfg(feature = "uspace")]
+#[def_trap_handler]
+pub static EXCEPTION: [fn(ExceptionKind, VirtAddr, VirtAddr) -> bool];
+
+/// A slice of functions called when a trap is taken from user space, before
+/// it's handled.
+#[cfg(feature = "uspace")]
+#[def_trap_handler]
+pub static USER_ENTRY: [fn()];
+
+/// A slice of functions called before a trap returns to user space.
+#[cfg(feature = "uspace")]
+#[def_trap_handler]
+pub static USER_RETURN: [fn()];
+
+/// The kind of an exception raised by a user program, see [`EXCEPTION`].
+#[cfg(feature = "uspace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionKind {
+    /// An instruction which doesn't exist or isn't allowed in user mode,
+    /// i.e. `SIGILL`.
+    IllegalInstruction,
+    /// A memory access which is not aligned, i.e. `SIGBUS`.
+    Misaligned,
+    /// An access to an illegal address or out of the bounds of a bounds check
+    /// instruction, i.e. `SIGSEGV`.
+    BadAddress,
+}
 
 #[allow(unused_macros)]
 macro_rules! handle_trap {
@@ -40,6 +75,27 @@ macro_rules! handle_trap {
 
 /// Call the external syscall handler.
 #[cfg(feature = "uspace")]
-pub(crate) fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
+pub(crate) fn handle_syscall(tf: &mut TrapFrame, syscall_num: usize) -> isize {
     SYSCALL[0](tf, syscall_num)
 }
+
+/// Call the external handlers when entering the kernel from user space.
+///
+/// It should be cheap, since it's called on every trap from user space.
+#[cfg(feature = "uspace")]
+pub(crate) fn handle_user_entry() {
+    for func in USER_ENTRY.iter() {
+        func();
+    }
+}
+
+/// Call the external handlers before returning to user space.
+///
+/// It's the point where a user task can be rescheduled if its time slice has
+/// run out, since there is no lock held by the kernel on this path.
+#[cfg(feature = "uspace")]
+pub(crate) fn handle_user_return() {
+    for func in USER_RETURN.iter() {
+        func();
+    }
+}
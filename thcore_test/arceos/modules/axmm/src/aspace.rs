@@ -1,6 +1,5 @@
 use core::fmt;
 
-use alloc::vec;
 use axerrno::{AxError, AxResult, ax_err};
 use axhal::mem::phys_to_virt;
 use axhal::paging::{MappingFlags, PageTable};
@@ -9,7 +8,7 @@ use memory_addr::{
 };
 use memory_set::{MemoryArea, MemorySet};
 
-use crate::backend::Backend;
+use crate::backend::{Backend, FileRegion, put_frame, share_frame};
 use crate::{KERNEL_ASPACE, mapping_err_to_ax_err};
 
 /// The virtual memory address space.
@@ -51,6 +50,24 @@ impl AddrSpace {
             .contains_range(VirtAddrRange::from_start_size(start, size))
     }
 
+    /// Returns the number of pages mapped to frames, i.e., the resident set
+    /// size in pages, not including the lazy pages not populated yet.
+    pub fn resident_pages(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| {
+                PageIter4K::new(area.start(), area.end())
+                    .expect("areas are aligned to 4K")
+                    .filter(|&vaddr| {
+                        self.pt
+                            .query(vaddr)
+                            .is_ok_and(|(_, flags, _)| !flags.is_empty())
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
     /// Creates a new empty address space.
     pub(crate) fn new_empty(base: VirtAddr, size: usize) -> AxResult<Self> {
         Ok(Self {
@@ -89,6 +106,41 @@ impl AddrSpace {
         self.areas.find_free_area(hint, size, limit)
     }
 
+    /// Returns the virtual address range of the area containing `vaddr`.
+    ///
+    /// Returns None if `vaddr` is not in any mapped area.
+    pub fn find_area_range(&self, vaddr: VirtAddr) -> Option<VirtAddrRange> {
+        self.areas
+            .find(vaddr)
+            .map(|area| VirtAddrRange::from_start_size(area.start(), area.size()))
+    }
+
+    /// Returns the mapping flags of the area containing `vaddr`, if any.
+    pub fn find_area_flags(&self, vaddr: VirtAddr) -> Option<MappingFlags> {
+        self.areas.find(vaddr).map(|area| area.flags())
+    }
+
+    /// Checks whether the given address range is all mapped, and every area
+    /// in it allows the access given by `access_flags`.
+    pub fn can_access_range(
+        &self,
+        start: VirtAddr,
+        size: usize,
+        access_flags: MappingFlags,
+    ) -> bool {
+        let Some(end) = start.as_usize().checked_add(size) else {
+            return false;
+        };
+        let mut start = start;
+        while start.as_usize() < end {
+            match self.areas.find(start) {
+                Some(area) if area.flags().contains(access_flags) => start = area.end(),
+                _ => return false,
+            }
+        }
+        true
+    }
+
     /// Add a new linear mapping.
     ///
     /// See [`Backend`] for more details about the mapping backends.
@@ -148,7 +200,36 @@ impl AddrSpace {
         Ok(())
     }
 
-    /// Add a new zero-initialized allocation mapping.
+    /// Add a new private file mapping of `region`, whose pages are read from
+    /// the file on demand.
+    ///
+    /// See [`Backend`] for more details about the mapping backends.
+    ///
+    /// Returns an error if the address range is out of the address space or not
+    /// aligned.
+    pub fn map_file(
+        &mut self,
+        start: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+        region: FileRegion,
+    ) -> AxResult {
+        if !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !start.is_aligned_4k() || !is_aligned_4k(size) {
+            return ax_err!(InvalidInput, "address not aligned");
+        }
+
+        let area = MemoryArea::new(start, size, flags, Backend::new_file(region));
+        self.areas
+            .map(area, &mut self.pt, false)
+            .map_err(mapping_err_to_ax_err)?;
+        Ok(())
+    }
+
+    /// Populates the lazy mappings in the given range, so that they can be
+    /// accessed by the kernel without page faults.
     pub fn alloc_for_lazy(&mut self, start: VirtAddr, size: usize) -> AxResult {
         let end = (start + size).align_up_4k();
         let mut start = start.align_down_4k();
@@ -158,18 +239,23 @@ impl AddrSpace {
         }
         while let Some(area) = self.areas.find(start) {
             let area_backend = area.backend();
-            if let Backend::Alloc { populate } = area_backend {
-                if !*populate {
-                    let count = (area.end().min(end) - start).align_up_4k() / PAGE_SIZE_4K;
-                    for i in 0..count {
-                        let addr = start + i * PAGE_SIZE_4K;
-                        area_backend.handle_page_fault_alloc(
-                            addr,
-                            area.flags(),
-                            &mut self.pt,
-                            *populate,
-                        );
+            if matches!(
+                area_backend,
+                Backend::Alloc { populate: false } | Backend::File { .. }
+            ) {
+                let count = (area.end().min(end) - start).align_up_4k() / PAGE_SIZE_4K;
+                for i in 0..count {
+                    let addr = start + i * PAGE_SIZE_4K;
+                    // Skip the pages that have been populated, or their
+                    // contents will be lost.
+                    if self
+                        .pt
+                        .query(addr)
+                        .is_ok_and(|(_, flags, _)| !flags.is_empty())
+                    {
+                        continue;
                     }
+                    area_backend.handle_page_fault(addr, area.flags(), &mut self.pt);
                 }
             }
             start = area.end();
@@ -349,6 +435,9 @@ impl AddrSpace {
 
     /// 克隆 AddrSpace。这将创建一个新的页表，并将旧页表中的所有区域（包括内核区域）映射到新的页表中，但仅将用户区域的映射到新的 MemorySet 中。
     ///
+    /// 用户区域中已分配的页以写时复制 (copy-on-write) 的方式与新的地址空间共享：
+    /// 两边的映射都被设为只读，直到其中一方写入时才复制出私有的页。
+    ///
     /// 如果发生错误，新创建的 MemorySet 将被丢弃并返回错误。
     pub fn clone_or_err(&mut self) -> AxResult<Self> {
         // 由于要克隆的这个地址空间可能是用户空间，而用户空间在一开始创建时不会在MemorySet中管理内核区域，而是直接把相关的页表项复制到了新页表中，所以在MemorySet中没有内核区域，需要另外处理。
@@ -366,41 +455,58 @@ impl AddrSpace {
 
         // 创建一个新的 MemorySet 并将原始区域映射到新的页表中。
         let mut new_areas = MemorySet::new();
-        let mut buf = vec![0u8; PAGE_SIZE_4K];
         for area in self.areas.iter() {
-            let new_area = MemoryArea::new(
-                area.start(),
-                area.size(),
-                area.flags(),
-                area.backend().clone(),
-            );
+            // 线性映射的区域与原区域共享相同的物理页，直接克隆即可。
+            if let Backend::Linear { .. } = area.backend() {
+                let new_area = MemoryArea::new(
+                    area.start(),
+                    area.size(),
+                    area.flags(),
+                    area.backend().clone(),
+                );
+                new_areas
+                    .map(new_area, &mut new_pt, false)
+                    .map_err(mapping_err_to_ax_err)?;
+                continue;
+            }
+
+            // 新区域先映射为空的页表项，再指向与原区域共享的页，
+            // 未分配的页仍然按需分配（文件映射的页仍然按需从文件读取）。
+            let new_backend = match area.backend() {
+                backend @ Backend::File { .. } => backend.clone(),
+                _ => Backend::new_alloc(false),
+            };
+            let new_area = MemoryArea::new(area.start(), area.size(), area.flags(), new_backend);
             new_areas
                 .map(new_area, &mut new_pt, false)
                 .map_err(mapping_err_to_ax_err)?;
-            // 将原区域的数据复制到新区域中。
-            // buf.resize(buf.capacity().max(area.size()), 0);
-            buf.resize(area.size(), 0);
-
-            // info!("clone_or_err: self.areas: {:?}, {:?}", area.start(), area.size());
-
-            self.read(area.start(), &mut buf).inspect_err(|_| {
-                new_areas.clear(&mut new_pt).unwrap();
-            })?;
-
-            Self::process_area_data_with_page_table(
-                &new_pt,
-                &self.va_range,
-                area.start(),
-                area.size(),
-                |dst, offset, write_size| unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        buf.as_ptr().add(offset),
-                        dst.as_mut_ptr(),
-                        write_size,
-                    );
-                },
-            )?;
+            for vaddr in PageIter4K::new(area.start(), area.end()).unwrap() {
+                let (frame, flags) = match self.pt.query(vaddr) {
+                    Ok((frame, flags, _)) if !flags.is_empty() => (frame, flags),
+                    _ => continue,
+                };
+                let cow_flags = flags - MappingFlags::WRITE;
+                if flags.contains(MappingFlags::WRITE) {
+                    // 旧的可写页表项在循环结束后统一刷新 TLB。
+                    self.pt
+                        .protect(vaddr, cow_flags)
+                        .map_err(|_| AxError::BadState)?
+                        .1
+                        .ignore();
+                }
+                share_frame(frame);
+                if new_pt.remap(vaddr, frame, cow_flags).is_err() {
+                    put_frame(frame);
+                    new_areas.clear(&mut new_pt).unwrap();
+                    return ax_err!(BadState, "failed to share frame");
+                }
+            }
         }
+        // 其他 CPU 上可能还缓存着降级前的可写表项，需要一并刷新。
+        #[cfg(feature = "smp")]
+        axhal::mp::flush_tlb_all_cpus();
+        #[cfg(not(feature = "smp"))]
+        axhal::arch::flush_tlb(None);
         // info!("clone_or_err: self.areas: out");
         Ok(Self {
             va_range: self.va_range,
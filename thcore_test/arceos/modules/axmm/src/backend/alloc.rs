@@ -1,11 +1,19 @@
+use alloc::collections::BTreeMap;
+
 use axalloc::global_allocator;
 use axhal::mem::{phys_to_virt, virt_to_phys};
 use axhal::paging::{MappingFlags, PageSize, PageTable};
-use memory_addr::{PAGE_SIZE_4K, PageIter4K, PhysAddr, VirtAddr};
+use kspin::SpinNoIrq;
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, PageIter4K, PhysAddr, VirtAddr};
 
 use super::Backend;
 
-fn alloc_frame(zeroed: bool) -> Option<PhysAddr> {
+/// Reference counts of the frames shared by copy-on-write mappings.
+///
+/// A frame that is not in the map is owned by a single mapping.
+static SHARED_FRAMES: SpinNoIrq<BTreeMap<PhysAddr, usize>> = SpinNoIrq::new(BTreeMap::new());
+
+pub(crate) fn alloc_frame(zeroed: bool) -> Option<PhysAddr> {
     let vaddr = VirtAddr::from(global_allocator().alloc_pages(1, PAGE_SIZE_4K).ok()?);
     if zeroed {
         unsafe { core::ptr::write_bytes(vaddr.as_mut_ptr(), 0, PAGE_SIZE_4K) };
@@ -19,6 +27,28 @@ fn dealloc_frame(frame: PhysAddr) {
     global_allocator().dealloc_pages(vaddr.as_usize(), 1);
 }
 
+/// Adds a reference to `frame`, which is going to be shared by one more
+/// mapping.
+pub(crate) fn share_frame(frame: PhysAddr) {
+    *SHARED_FRAMES.lock().entry(frame).or_insert(1) += 1;
+}
+
+/// Drops a reference to `frame`, and deallocates it if it is the last one.
+pub(crate) fn put_frame(frame: PhysAddr) {
+    let mut shared = SHARED_FRAMES.lock();
+    match shared.get_mut(&frame) {
+        Some(count) if *count > 2 => *count -= 1,
+        // Only one mapping is left, the frame is owned by it now.
+        Some(_) => {
+            shared.remove(&frame);
+        }
+        None => {
+            drop(shared);
+            dealloc_frame(frame);
+        }
+    }
+}
+
 impl Backend {
     /// Creates a new allocation mapping backend.
     pub const fn new_alloc(populate: bool) -> Self {
@@ -77,7 +107,7 @@ impl Backend {
                     return false;
                 }
                 tlb.flush();
-                dealloc_frame(frame);
+                put_frame(frame);
             } else {
                 // Deallocation is needn't if the page is not mapped.
             }
@@ -92,6 +122,15 @@ impl Backend {
         pt: &mut PageTable,
         populate: bool,
     ) -> bool {
+        if let Ok((frame, flags, _)) = pt.query(vaddr) {
+            if !flags.is_empty() {
+                // The page is present, so only a write to a copy-on-write page
+                // can be handled. Other faults are genuine permission errors.
+                return orig_flags.contains(MappingFlags::WRITE)
+                    && !flags.contains(MappingFlags::WRITE)
+                    && Self::handle_cow_fault(vaddr, frame, orig_flags, pt);
+            }
+        }
         if populate {
             false // Populated mappings should not trigger page faults.
         } else if let Some(frame) = alloc_frame(true) {
@@ -105,4 +144,57 @@ impl Backend {
             false
         }
     }
+    /// Handles a write fault on a copy-on-write page mapped to `frame`.
+    ///
+    /// The faulting mapping gets a private copy of the frame, unless it is the
+    /// last one sharing the frame, in which case it is just made writable.
+    ///
+    /// The reference count is checked and dropped in one step under the lock
+    /// of [`SHARED_FRAMES`], so that a fault on the frame in another address
+    /// space can't change it in between.
+    pub(crate) fn handle_cow_fault(
+        vaddr: VirtAddr,
+        frame: PhysAddr,
+        orig_flags: MappingFlags,
+        pt: &mut PageTable,
+    ) -> bool {
+        let vaddr = vaddr.align_down_4k();
+        let frame = frame.align_down_4k();
+        let mut shared = SHARED_FRAMES.lock();
+        let Some(count) = shared.get_mut(&frame) else {
+            drop(shared);
+            return pt
+                .protect(vaddr, orig_flags)
+                .map(|(_, tlb)| tlb.flush())
+                .is_ok();
+        };
+
+        let Some(new_frame) = alloc_frame(false) else {
+            return false;
+        };
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                phys_to_virt(frame).as_ptr(),
+                phys_to_virt(new_frame).as_mut_ptr(),
+                PAGE_SIZE_4K,
+            );
+        }
+        match pt.remap(vaddr, new_frame, orig_flags) {
+            Ok((_, tlb)) => {
+                tlb.flush();
+                if *count > 2 {
+                    *count -= 1;
+                } else {
+                    // Only one mapping is left, the frame is owned by it now.
+                    shared.remove(&frame);
+                }
+                true
+            }
+            Err(_) => {
+                drop(shared);
+                dealloc_frame(new_frame);
+                false
+            }
+        }
+    }
 }
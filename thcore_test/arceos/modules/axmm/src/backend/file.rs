@@ -0,0 +1,125 @@
+use alloc::sync::Arc;
+
+use axerrno::AxResult;
+use axhal::mem::phys_to_virt;
+use axhal::paging::{MappingFlags, PageTable};
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr, is_aligned_4k};
+
+use super::{Backend, alloc::alloc_frame};
+
+/// A file that the pages of file-backed mappings are read from.
+pub trait FileBacking: Send + Sync {
+    /// Reads the data at `offset` of the file into `buf`.
+    ///
+    /// Returns the number of bytes read, which is less than `buf.len()` only
+    /// at the end of the file.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize>;
+}
+
+/// A region of a file mapped to the virtual memory.
+///
+/// The region is shared by all the mappings created from it, including the
+/// ones split from the original mapping and the ones in forked address
+/// spaces, so the pages read from it can be cached and shared here later.
+pub struct FileRegion {
+    file: Arc<dyn FileBacking>,
+    /// The virtual address that `offset` of the file is mapped to.
+    start: VirtAddr,
+    /// The offset of the region in the file.
+    offset: u64,
+    /// The length of the region in the file. The mapped pages beyond it are
+    /// filled with zeros.
+    len: usize,
+}
+
+impl FileRegion {
+    /// Creates a region of `len` bytes at `offset` of `file`, which is mapped
+    /// at `start`.
+    ///
+    /// `start` and `offset` must be aligned to 4K.
+    pub fn new(file: Arc<dyn FileBacking>, start: VirtAddr, offset: u64, len: usize) -> Self {
+        assert!(start.is_aligned_4k() && is_aligned_4k(offset as usize));
+        Self {
+            file,
+            start,
+            offset,
+            len,
+        }
+    }
+
+    /// Fills the page at `vaddr` with the file data, and zeros beyond the
+    /// end of the region.
+    fn read_page(&self, vaddr: VirtAddr, page: &mut [u8]) -> AxResult {
+        let pos = vaddr.align_down_4k() - self.start;
+        let len = self.len.saturating_sub(pos).min(PAGE_SIZE_4K);
+        let mut read = 0;
+        while read < len {
+            let offset = self.offset + (pos + read) as u64;
+            match self.file.read_at(offset, &mut page[read..len])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        page[read..].fill(0);
+        Ok(())
+    }
+}
+
+impl Backend {
+    /// Creates a new file mapping backend of `region`.
+    pub fn new_file(region: FileRegion) -> Self {
+        Self::File {
+            region: Arc::new(region),
+        }
+    }
+
+    pub(crate) fn map_file(
+        &self,
+        start: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+        pt: &mut PageTable,
+    ) -> bool {
+        debug!("map_file: [{:#x}, {:#x}) {:?}", start, start + size, flags);
+        // Map to a empty entry for on-demand mapping.
+        let flags = MappingFlags::empty();
+        pt.map_region(start, |_| 0.into(), size, flags, false, false)
+            .map(|tlb| tlb.ignore())
+            .is_ok()
+    }
+
+    pub(crate) fn handle_page_fault_file(
+        &self,
+        vaddr: VirtAddr,
+        orig_flags: MappingFlags,
+        pt: &mut PageTable,
+        region: &FileRegion,
+    ) -> bool {
+        if let Ok((frame, flags, _)) = pt.query(vaddr) {
+            if !flags.is_empty() {
+                // The page has been read, only a write to a copy-on-write page
+                // can be handled.
+                return orig_flags.contains(MappingFlags::WRITE)
+                    && !flags.contains(MappingFlags::WRITE)
+                    && Self::handle_cow_fault(vaddr, frame, orig_flags, pt);
+            }
+        }
+        let Some(frame) = alloc_frame(false) else {
+            return false;
+        };
+        let page = unsafe {
+            core::slice::from_raw_parts_mut(phys_to_virt(frame).as_mut_ptr(), PAGE_SIZE_4K)
+        };
+        if let Err(err) = region.read_page(vaddr, page) {
+            warn!("Failed to read the file page at {:#x}: {:?}", vaddr, err);
+            super::put_frame(frame);
+            return false;
+        }
+        if orig_flags.contains(MappingFlags::EXECUTE) {
+            axhal::arch::flush_icache_all();
+        }
+        pt.remap(vaddr, frame, orig_flags)
+            .map(|(_, tlb)| tlb.flush())
+            .is_ok()
+    }
+}
@@ -1,20 +1,28 @@
 //! Memory mapping backends.
 
+use ::alloc::sync::Arc;
 use axhal::paging::{MappingFlags, PageTable};
 use memory_addr::VirtAddr;
 use memory_set::MappingBackend;
 
 mod alloc;
+mod file;
 mod linear;
 
+pub(crate) use self::alloc::{put_frame, share_frame};
+pub use self::file::{FileBacking, FileRegion};
+
 /// A unified enum type for different memory mapping backends.
 ///
-/// Currently, two backends are implemented:
+/// Currently, three backends are implemented:
 ///
 /// - **Linear**: used for linear mappings. The target physical frames are
 ///   contiguous and their addresses should be known when creating the mapping.
 /// - **Allocation**: used in general, or for lazy mappings. The target physical
 ///   frames are obtained from the global allocator.
+/// - **File**: used for private file mappings. The target physical frames are
+///   obtained from the global allocator and filled with the file data on
+///   demand.
 #[derive(Clone)]
 pub enum Backend {
     /// Linear mapping backend.
@@ -36,6 +44,15 @@ pub enum Backend {
         /// Whether to populate the physical frames when creating the mapping.
         populate: bool,
     },
+    /// File mapping backend.
+    ///
+    /// The physical frames are allocated and read from the file on demand (by
+    /// handling page faults). Writes are private to the mapping and never go
+    /// back to the file.
+    File {
+        /// The region of the file that is mapped.
+        region: Arc<FileRegion>,
+    },
 }
 
 impl MappingBackend for Backend {
@@ -46,6 +63,7 @@ impl MappingBackend for Backend {
         match *self {
             Self::Linear { pa_va_offset } => self.map_linear(start, size, flags, pt, pa_va_offset),
             Self::Alloc { populate } => self.map_alloc(start, size, flags, pt, populate),
+            Self::File { .. } => self.map_file(start, size, flags, pt),
         }
     }
 
@@ -53,6 +71,8 @@ impl MappingBackend for Backend {
         match *self {
             Self::Linear { pa_va_offset } => self.unmap_linear(start, size, pt, pa_va_offset),
             Self::Alloc { populate } => self.unmap_alloc(start, size, pt, populate),
+            // The frames are allocated in the same way as lazy allocation mappings.
+            Self::File { .. } => self.unmap_alloc(start, size, pt, false),
         }
     }
 
@@ -77,10 +97,13 @@ impl Backend {
         orig_flags: MappingFlags,
         page_table: &mut PageTable,
     ) -> bool {
-        match *self {
+        match self {
             Self::Linear { .. } => false, // Linear mappings should not trigger page faults.
             Self::Alloc { populate } => {
-                self.handle_page_fault_alloc(vaddr, orig_flags, page_table, populate)
+                self.handle_page_fault_alloc(vaddr, orig_flags, page_table, *populate)
+            }
+            Self::File { region } => {
+                self.handle_page_fault_file(vaddr, orig_flags, page_table, region)
             }
         }
     }
@@ -10,7 +10,7 @@ mod aspace;
 mod backend;
 
 pub use self::aspace::AddrSpace;
-pub use self::backend::Backend;
+pub use self::backend::{Backend, FileBacking, FileRegion};
 
 use axerrno::{AxError, AxResult};
 use axhal::mem::phys_to_virt;
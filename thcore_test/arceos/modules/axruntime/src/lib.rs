@@ -254,12 +254,15 @@ fn init_interrupt() {
         axhal::time::set_oneshot_timer(deadline);
     }
 
-    axhal::irq::register_handler(TIMER_IRQ_NUM, || {
+    axhal::irq::register_handler(TIMER_IRQ_NUM, |_| {
         update_timer();
         #[cfg(feature = "multitask")]
         axtask::on_timer_tick();
     });
 
+    #[cfg(feature = "smp")]
+    axhal::mp::init_tlb_shootdown();
+
     // Enable IRQs before starting app
     axhal::arch::enable_irqs();
 }
@@ -114,6 +114,23 @@ pub fn spawn_task(task: TaskInner) -> AxTaskRef {
     task_ref
 }
 
+/// Adds the given task to the run queue with the given priority, returns the
+/// task reference.
+///
+/// The run queue is selected according to the CPU affinity of the task, so
+/// the affinity should be set by [`TaskInner::set_cpumask`] before calling
+/// this function.
+///
+/// Returns `None` if the underlying scheduler does not accept the priority
+/// (see [`set_priority`]), e.g. the FIFO and round-robin schedulers accept
+/// none. The task is not spawned then.
+pub fn spawn_task_with_priority(task: TaskInner, prio: isize) -> Option<AxTaskRef> {
+    let task_ref = task.into_arc();
+    select_run_queue::<NoPreemptIrqSave>(&task_ref)
+        .add_task_with_priority(task_ref.clone(), prio)
+        .then_some(task_ref)
+}
+
 /// Spawns a new task with the given parameters.
 ///
 /// Returns the task reference.
@@ -184,12 +201,34 @@ pub fn set_current_affinity(cpumask: AxCpuMask) -> bool {
     }
 }
 
+/// Interrupt the given task, waking it up if it's blocked, e.g., sleeping or
+/// waiting in a [`WaitQueue`].
+///
+/// The task is interrupted from then on: its waits that check
+/// [`TaskInner::is_interrupted`] give up, and to the others, it's a spurious
+/// wakeup like a timeout.
+pub fn interrupt(task: &AxTaskRef) {
+    task.set_interrupted();
+    select_run_queue::<NoPreemptIrqSave>(task).unblock_task(task.clone(), false);
+}
+
 /// Current task gives up the CPU time voluntarily, and switches to another
 /// ready task.
 pub fn yield_now() {
     current_run_queue::<NoPreemptIrqSave>().yield_current()
 }
 
+/// Reschedules if the time slice of the current task has run out.
+///
+/// It's intended to be called where no lock is held, e.g., just before
+/// returning to user space, so that tasks that never trap into the kernel
+/// voluntarily can still be preempted.
+#[cfg(feature = "preempt")]
+#[doc(cfg(feature = "preempt"))]
+pub fn resched_if_needed() {
+    TaskInner::current_check_preempt_pending();
+}
+
 /// Current task is going to sleep for the given duration.
 ///
 /// If the feature `irq` is not enabled, it uses busy-wait instead.
@@ -240,6 +240,26 @@ impl<G: BaseGuard> AxRunQueueRef<'_, G> {
         self.inner.scheduler.lock().add_task(task);
     }
 
+    /// Adds a task to the scheduler with the given priority.
+    ///
+    /// Returns `false` if the priority is not accepted by the scheduler, the
+    /// task is not added in this case.
+    pub fn add_task_with_priority(&mut self, task: AxTaskRef, prio: isize) -> bool {
+        debug!(
+            "task add: {} on run_queue {} with priority {}",
+            task.id_name(),
+            self.inner.cpu_id,
+            prio
+        );
+        assert!(task.is_ready());
+        let mut scheduler = self.inner.scheduler.lock();
+        if !scheduler.set_priority(&task, prio) {
+            return false;
+        }
+        scheduler.add_task(task);
+        true
+    }
+
     /// Unblock one task by inserting it into the run queue.
     ///
     /// This function does nothing if the task is not in [`TaskState::Blocked`],
@@ -427,7 +447,8 @@ impl<G: BaseGuard> CurrentRunQueueRef<'_, G> {
         assert!(!curr.is_idle());
 
         let now = axhal::time::wall_time();
-        if now < deadline {
+        // An interrupted task doesn't sleep any more.
+        if now < deadline && !curr.is_interrupted() {
             crate::timers::set_alarm_wakeup(deadline, curr.clone());
             curr.set_state(TaskState::Blocked);
             self.inner.resched();
@@ -540,6 +561,10 @@ impl AxRunQueue {
             return;
         }
 
+        let now = axhal::time::monotonic_time_nanos();
+        prev_task.account_switch_out(now);
+        next_task.account_switch_in(now);
+
         // Claim the task as running, we do this before switching to it
         // such that any running task will have this set.
         #[cfg(feature = "smp")]
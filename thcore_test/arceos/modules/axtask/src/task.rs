@@ -1,7 +1,7 @@
 use alloc::{boxed::Box, string::String, sync::Arc};
 use core::ops::Deref;
 use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicU64, Ordering};
-use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
+use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull, time::Duration};
 
 #[cfg(feature = "preempt")]
 use core::sync::atomic::AtomicUsize;
@@ -51,6 +51,9 @@ pub struct TaskInner {
     /// Mark whether the task is in the wait queue.
     in_wait_queue: AtomicBool,
 
+    /// Mark whether the task has been interrupted by [`interrupt`](crate::interrupt).
+    interrupted: AtomicBool,
+
     /// Used to indicate whether the task is running on a CPU.
     #[cfg(feature = "smp")]
     on_cpu: AtomicBool,
@@ -69,6 +72,11 @@ pub struct TaskInner {
     exit_code: AtomicI32,
     wait_for_exit: WaitQueue,
 
+    /// The time in nanoseconds the task has run on CPUs before its current run.
+    cpu_time_ns: AtomicU64,
+    /// The monotonic time in nanoseconds when the task was switched to.
+    switched_in_ns: AtomicU64,
+
     kstack: Option<TaskStack>,
     ctx: UnsafeCell<TaskContext>,
     task_ext: AxTaskExt,
@@ -226,10 +234,28 @@ impl TaskInner {
         None
     }
 
+    /// Whether the task has been interrupted by [`interrupt`](crate::interrupt),
+    /// so its blocking operations should give up.
+    #[inline]
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Acquire)
+    }
+
     /// Returns the exit code of the task.
     pub fn exit_code(&self) -> i32 {
         self.exit_code.load(Ordering::Acquire)
     }
+
+    /// Returns the time the task has run on CPUs, including the current run
+    /// if it's running.
+    pub fn cpu_time(&self) -> Duration {
+        let mut nanos = self.cpu_time_ns.load(Ordering::Acquire);
+        if self.is_running() {
+            let switched_in = self.switched_in_ns.load(Ordering::Acquire);
+            nanos += axhal::time::monotonic_time_nanos().saturating_sub(switched_in);
+        }
+        Duration::from_nanos(nanos)
+    }
 }
 
 // private methods
@@ -245,6 +271,7 @@ impl TaskInner {
             // By default, the task is allowed to run on all CPUs.
             cpumask: SpinNoIrq::new(AxCpuMask::full()),
             in_wait_queue: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
             #[cfg(feature = "irq")]
             timer_ticket_id: AtomicU64::new(0),
             #[cfg(feature = "smp")]
@@ -255,6 +282,8 @@ impl TaskInner {
             preempt_disable_count: AtomicUsize::new(0),
             exit_code: AtomicI32::new(0),
             wait_for_exit: WaitQueue::new(),
+            cpu_time_ns: AtomicU64::new(0),
+            switched_in_ns: AtomicU64::new(0),
             kstack: None,
             ctx: UnsafeCell::new(TaskContext::new()),
             task_ext: AxTaskExt::empty(),
@@ -274,6 +303,7 @@ impl TaskInner {
     pub(crate) fn new_init(name: String) -> Self {
         let mut t = Self::new_common(TaskId::new(), name);
         t.is_init = true;
+        t.switched_in_ns = AtomicU64::new(axhal::time::monotonic_time_nanos());
         #[cfg(feature = "smp")]
         t.set_on_cpu(true);
         if t.name() == "idle" {
@@ -313,6 +343,20 @@ impl TaskInner {
             .is_ok()
     }
 
+    /// Ends the current run of the task at `now` on a context switch.
+    #[inline]
+    pub(crate) fn account_switch_out(&self, now: u64) {
+        let switched_in = self.switched_in_ns.load(Ordering::Acquire);
+        self.cpu_time_ns
+            .fetch_add(now.saturating_sub(switched_in), Ordering::AcqRel);
+    }
+
+    /// Starts a run of the task at `now` on a context switch.
+    #[inline]
+    pub(crate) fn account_switch_in(&self, now: u64) {
+        self.switched_in_ns.store(now, Ordering::Release);
+    }
+
     #[inline]
     pub(crate) fn is_running(&self) -> bool {
         matches!(self.state(), TaskState::Running)
@@ -333,6 +377,11 @@ impl TaskInner {
         self.is_idle
     }
 
+    #[inline]
+    pub(crate) fn set_interrupted(&self) {
+        self.interrupted.store(true, Ordering::Release);
+    }
+
     #[inline]
     pub(crate) fn in_wait_queue(&self) -> bool {
         self.in_wait_queue.load(Ordering::Acquire)
@@ -397,7 +446,7 @@ impl TaskInner {
     }
 
     #[cfg(feature = "preempt")]
-    fn current_check_preempt_pending() {
+    pub(crate) fn current_check_preempt_pending() {
         use kernel_guard::NoPreemptIrqSave;
         let curr = crate::current();
         if curr.need_resched.load(Ordering::Acquire) && curr.can_preempt(0) {
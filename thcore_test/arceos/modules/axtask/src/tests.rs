@@ -63,6 +63,36 @@ fn test_fp_state_switch() {
     }
 }
 
+#[test]
+fn test_fp_state_alternate() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    const ROUNDS: usize = 100;
+    const STEPS: [f64; 2] = [0.5, -0.25];
+    static FINISHED_TASKS: AtomicUsize = AtomicUsize::new(0);
+
+    // Each task keeps its sum in a floating-point register across the
+    // switches to the other one.
+    for (i, step) in STEPS.into_iter().enumerate() {
+        axtask::spawn(move || {
+            let mut sum = i as f64;
+            for _ in 0..ROUNDS {
+                sum = sum * 2.0 + step;
+                axtask::yield_now();
+                sum = (sum - step) / 2.0 + step;
+            }
+
+            println!("fp_state_alternate: Sum {} = {}", i, sum);
+            assert_eq!(sum, i as f64 + step * ROUNDS as f64);
+            FINISHED_TASKS.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    while FINISHED_TASKS.load(Ordering::Relaxed) < STEPS.len() {
+        axtask::yield_now();
+    }
+}
+
 #[test]
 fn test_wait_queue() {
     let _lock = SERIAL.lock();
@@ -128,3 +158,24 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_interrupt() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static WQ: WaitQueue = WaitQueue::new();
+
+    let task = axtask::spawn(|| {
+        // Nothing notifies the queue, only the interruption wakes it up.
+        WQ.wait_until(|| current().is_interrupted());
+        assert!(!current().in_wait_queue());
+        axtask::exit(42);
+    });
+    axtask::yield_now();
+    assert!(!task.is_interrupted());
+
+    axtask::interrupt(&task);
+    assert!(task.is_interrupted());
+    assert_eq!(task.join(), Some(42));
+}
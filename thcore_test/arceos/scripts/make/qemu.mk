@@ -28,6 +28,7 @@ endif
 
 qemu_args-x86_64 := \
   -machine $(machine) \
+  -device isa-debug-exit,iobase=0xf4,iosize=0x04 \
   -kernel $(OUT_ELF)
 
 qemu_args-riscv64 := \
@@ -38,6 +39,7 @@ qemu_args-riscv64 := \
 qemu_args-aarch64 := \
   -cpu cortex-a72 \
   -machine $(machine) \
+  -semihosting \
   -kernel $(OUT_BIN)
 
 qemu_args-loongarch64 := \
@@ -73,29 +73,91 @@ pub enum WaitStatus {
 }
 #[repr(C)]
 pub struct Tms {
-    /// 进程用户态执行时间，单位为us
+    /// 进程用户态执行时间，单位为 clock tick
     pub tms_utime: usize,
-    /// 进程内核态执行时间，单位为us
+    /// 进程内核态执行时间，单位为 clock tick
     pub tms_stime: usize,
-    /// 子进程用户态执行时间和，单位为us
+    /// 子进程用户态执行时间和，单位为 clock tick
     pub tms_cutime: usize,
-    /// 子进程内核态执行时间和，单位为us
+    /// 子进程内核态执行时间和，单位为 clock tick
     pub tms_cstime: usize,
 }
 
+/// `struct timeval` used by [`Rusage`].
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct TimeVal {
+    /// 秒
+    pub tv_sec: i64,
+    /// 微秒
+    pub tv_usec: i64,
+}
+
+impl From<core::time::Duration> for TimeVal {
+    fn from(duration: core::time::Duration) -> Self {
+        Self {
+            tv_sec: duration.as_secs() as i64,
+            tv_usec: duration.subsec_micros() as i64,
+        }
+    }
+}
+
+/// `struct rusage` of Linux, filled by `getrusage`.
+///
+/// Only the CPU times and the resident set size are reported, the other
+/// fields are zero.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct Rusage {
+    /// User CPU time used
+    pub ru_utime: TimeVal,
+    /// System CPU time used
+    pub ru_stime: TimeVal,
+    /// Maximum resident set size in kilobytes
+    pub ru_maxrss: i64,
+    /// Integral shared memory size
+    pub ru_ixrss: i64,
+    /// Integral unshared data size
+    pub ru_idrss: i64,
+    /// Integral unshared stack size
+    pub ru_isrss: i64,
+    /// Page reclaims (soft page faults)
+    pub ru_minflt: i64,
+    /// Page faults (hard page faults)
+    pub ru_majflt: i64,
+    /// Swaps
+    pub ru_nswap: i64,
+    /// Block input operations
+    pub ru_inblock: i64,
+    /// Block output operations
+    pub ru_oublock: i64,
+    /// IPC messages sent
+    pub ru_msgsnd: i64,
+    /// IPC messages received
+    pub ru_msgrcv: i64,
+    /// Signals received
+    pub ru_nsignals: i64,
+    /// Voluntary context switches
+    pub ru_nvcsw: i64,
+    /// Involuntary context switches
+    pub ru_nivcsw: i64,
+}
+
+const _: () = assert!(core::mem::size_of::<Rusage>() == 144);
+
 numeric_enum_macro::numeric_enum! {
     #[repr(i32)]
     #[allow(non_camel_case_types)]
     #[derive(Eq, PartialEq, Debug, Clone, Copy)]
     pub enum TimerType {
-    /// 表示目前没有任何计时器(不在linux规范中，是os自己规定的)
-    NONE = -1,
-    /// 统计系统实际运行时间
-    REAL = 0,
-    /// 统计用户态运行时间
-    VIRTUAL = 1,
-    /// 统计进程的所有用户态/内核态运行时间
-    PROF = 2,
+        /// 表示目前没有任何计时器(不在linux规范中，是os自己规定的)
+        NONE = -1,
+        /// 统计系统实际运行时间
+        REAL = 0,
+        /// 统计用户态运行时间
+        VIRTUAL = 1,
+        /// 统计进程的所有用户态/内核态运行时间
+        PROF = 2,
     }
 }
 
@@ -107,9 +169,14 @@ impl From<usize> for TimerType {
         }
     }
 }
+/// The time statistics of a task.
+///
+/// Only the user time is accounted here, on the trap path. The system time is
+/// derived from the time the task has run on CPUs.
 pub struct TimeStat {
     utime_ns: usize,
-    stime_ns: usize,
+    cutime_ns: usize,
+    cstime_ns: usize,
     user_timestamp: usize,
     kernel_timestamp: usize,
     timer_type: TimerType,
@@ -127,7 +194,8 @@ impl TimeStat {
     pub fn new() -> Self {
         Self {
             utime_ns: 0,
-            stime_ns: 0,
+            cutime_ns: 0,
+            cstime_ns: 0,
             user_timestamp: 0,
             kernel_timestamp: 0,
             timer_type: TimerType::NONE,
@@ -136,20 +204,34 @@ impl TimeStat {
         }
     }
 
-    pub fn output(&self) -> (usize, usize) {
-        (self.utime_ns, self.stime_ns)
+    /// The time in the user mode, in nanoseconds.
+    pub fn utime_ns(&self) -> usize {
+        self.utime_ns
+    }
+
+    /// The user and system time of the reaped children, in nanoseconds.
+    pub fn children_ns(&self) -> (usize, usize) {
+        (self.cutime_ns, self.cstime_ns)
+    }
+
+    /// Add the user and system time of a reaped child, including those of its
+    /// own reaped children.
+    pub fn add_child(&mut self, utime_ns: usize, stime_ns: usize) {
+        self.cutime_ns += utime_ns;
+        self.cstime_ns += stime_ns;
     }
 
     pub fn reset(&mut self, current_timestamp: usize) {
         self.utime_ns = 0;
-        self.stime_ns = 0;
         self.user_timestamp = 0;
         self.kernel_timestamp = current_timestamp;
     }
 
+    /// Account the time since the last [`Self::switch_into_user_mode`] as the
+    /// user time.
     pub fn switch_into_kernel_mode(&mut self, current_timestamp: usize) {
         let now_time_ns = current_timestamp;
-        let delta = now_time_ns - self.kernel_timestamp;
+        let delta = now_time_ns - self.user_timestamp;
         self.utime_ns += delta;
         self.kernel_timestamp = now_time_ns;
         if self.timer_type != TimerType::NONE {
@@ -160,32 +242,12 @@ impl TimeStat {
     pub fn switch_into_user_mode(&mut self, current_timestamp: usize) {
         let now_time_ns = current_timestamp;
         let delta = now_time_ns - self.kernel_timestamp;
-        self.stime_ns += delta;
         self.user_timestamp = now_time_ns;
         if self.timer_type == TimerType::REAL || self.timer_type == TimerType::PROF {
             self.update_timer(delta);
         }
     }
 
-    pub fn switch_from_old_task(&mut self, current_timestamp: usize) {
-        let now_time_ns = current_timestamp;
-        let delta = now_time_ns - self.kernel_timestamp;
-        self.stime_ns += delta;
-        self.kernel_timestamp = now_time_ns;
-        if self.timer_type == TimerType::REAL || self.timer_type == TimerType::PROF {
-            self.update_timer(delta);
-        }
-    }
-
-    pub fn switch_to_new_task(&mut self, current_timestamp: usize) {
-        let now_time_ns = current_timestamp;
-        let delta = now_time_ns - self.kernel_timestamp;
-        self.kernel_timestamp = now_time_ns;
-        if self.timer_type == TimerType::REAL {
-            self.update_timer(delta);
-        }
-    }
-
     pub fn set_timer(
         &mut self,
         timer_interval_ns: usize,
@@ -207,3 +269,386 @@ impl TimeStat {
         }
     }
 }
+
+/// `struct stat` as laid out by musl on x86_64.
+#[cfg(target_arch = "x86_64")]
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct Kstat {
+    /// 设备
+    pub st_dev: u64,
+    /// inode 编号
+    pub st_ino: u64,
+    /// 硬链接数
+    pub st_nlink: u64,
+    /// 文件类型
+    pub st_mode: u32,
+    /// 用户id
+    pub st_uid: u32,
+    /// 用户组id
+    pub st_gid: u32,
+    /// padding
+    pub _pad0: u32,
+    /// 设备号
+    pub st_rdev: u64,
+    /// 文件大小
+    pub st_size: i64,
+    /// 块大小
+    pub st_blksize: i64,
+    /// 块个数
+    pub st_blocks: i64,
+    /// 最后一次访问时间(秒)
+    pub st_atime_sec: i64,
+    /// 最后一次访问时间(纳秒)
+    pub st_atime_nsec: i64,
+    /// 最后一次修改时间(秒)
+    pub st_mtime_sec: i64,
+    /// 最后一次修改时间(纳秒)
+    pub st_mtime_nsec: i64,
+    /// 最后一次改变状态时间(秒)
+    pub st_ctime_sec: i64,
+    /// 最后一次改变状态时间(纳秒)
+    pub st_ctime_nsec: i64,
+    /// 保留
+    pub _unused: [i64; 3],
+}
+
+/// `struct stat` as laid out by musl on the generic LP64 ABI
+/// (riscv64, aarch64 and loongarch64).
+#[cfg(not(target_arch = "x86_64"))]
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct Kstat {
+    /// 设备
+    pub st_dev: u64,
+    /// inode 编号
+    pub st_ino: u64,
+    /// 文件类型
+    pub st_mode: u32,
+    /// 硬链接数
+    pub st_nlink: u32,
+    /// 用户id
+    pub st_uid: u32,
+    /// 用户组id
+    pub st_gid: u32,
+    /// 设备号
+    pub st_rdev: u64,
+    /// padding
+    pub _pad0: u64,
+    /// 文件大小
+    pub st_size: i64,
+    /// 块大小
+    pub st_blksize: i32,
+    /// padding
+    pub _pad1: i32,
+    /// 块个数
+    pub st_blocks: i64,
+    /// 最后一次访问时间(秒)
+    pub st_atime_sec: i64,
+    /// 最后一次访问时间(纳秒)
+    pub st_atime_nsec: i64,
+    /// 最后一次修改时间(秒)
+    pub st_mtime_sec: i64,
+    /// 最后一次修改时间(纳秒)
+    pub st_mtime_nsec: i64,
+    /// 最后一次改变状态时间(秒)
+    pub st_ctime_sec: i64,
+    /// 最后一次改变状态时间(纳秒)
+    pub st_ctime_nsec: i64,
+    /// 保留
+    pub _unused: [u32; 2],
+}
+
+// Checked against `arch/*/bits/stat.h` of musl.
+#[cfg(target_arch = "x86_64")]
+const _: () = {
+    use core::mem::{offset_of, size_of};
+    assert!(size_of::<Kstat>() == 144);
+    assert!(offset_of!(Kstat, st_nlink) == 16);
+    assert!(offset_of!(Kstat, st_mode) == 24);
+    assert!(offset_of!(Kstat, st_rdev) == 40);
+    assert!(offset_of!(Kstat, st_size) == 48);
+    assert!(offset_of!(Kstat, st_blksize) == 56);
+    assert!(offset_of!(Kstat, st_blocks) == 64);
+    assert!(offset_of!(Kstat, st_atime_sec) == 72);
+    assert!(offset_of!(Kstat, st_mtime_sec) == 88);
+    assert!(offset_of!(Kstat, st_ctime_sec) == 104);
+};
+
+#[cfg(not(target_arch = "x86_64"))]
+const _: () = {
+    use core::mem::{offset_of, size_of};
+    assert!(size_of::<Kstat>() == 128);
+    assert!(offset_of!(Kstat, st_mode) == 16);
+    assert!(offset_of!(Kstat, st_nlink) == 20);
+    assert!(offset_of!(Kstat, st_rdev) == 32);
+    assert!(offset_of!(Kstat, st_size) == 48);
+    assert!(offset_of!(Kstat, st_blksize) == 56);
+    assert!(offset_of!(Kstat, st_blocks) == 64);
+    assert!(offset_of!(Kstat, st_atime_sec) == 72);
+    assert!(offset_of!(Kstat, st_mtime_sec) == 88);
+    assert!(offset_of!(Kstat, st_ctime_sec) == 104);
+};
+
+/// Timestamp used by [`StatX`].
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct StatxTimestamp {
+    /// 秒
+    pub tv_sec: i64,
+    /// 纳秒
+    pub tv_nsec: u32,
+    /// 保留
+    pub _reserved: i32,
+}
+
+/// statx - get file status (extended)
+/// Standard C library (libc, -lc)
+/// <https://man7.org/linux/man-pages/man2/statx.2.html>
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct StatX {
+    /// Bitmask of what information to get.
+    pub stx_mask: u32,
+    /// Block size for filesystem I/O.
+    pub stx_blksize: u32,
+    /// File attributes.
+    pub stx_attributes: u64,
+    /// Number of hard links.
+    pub stx_nlink: u32,
+    /// User ID of owner.
+    pub stx_uid: u32,
+    /// Group ID of owner.
+    pub stx_gid: u32,
+    /// File mode (permissions).
+    pub stx_mode: u16,
+    /// padding
+    pub _spare0: u16,
+    /// Inode number.
+    pub stx_ino: u64,
+    /// Total size, in bytes.
+    pub stx_size: u64,
+    /// Number of 512B blocks allocated.
+    pub stx_blocks: u64,
+    /// Mask to show what's supported in stx_attributes.
+    pub stx_attributes_mask: u64,
+    /// Last access timestamp.
+    pub stx_atime: StatxTimestamp,
+    /// Birth (creation) timestamp.
+    pub stx_btime: StatxTimestamp,
+    /// Last status change timestamp.
+    pub stx_ctime: StatxTimestamp,
+    /// Last modification timestamp.
+    pub stx_mtime: StatxTimestamp,
+    /// Major device ID (if special file).
+    pub stx_rdev_major: u32,
+    /// Minor device ID (if special file).
+    pub stx_rdev_minor: u32,
+    /// Major device ID of file system.
+    pub stx_dev_major: u32,
+    /// Minor device ID of file system.
+    pub stx_dev_minor: u32,
+    /// Mount ID.
+    pub stx_mnt_id: u64,
+    /// Memory alignment for direct I/O.
+    pub stx_dio_mem_align: u32,
+    /// Offset alignment for direct I/O.
+    pub stx_dio_offset_align: u32,
+    /// 保留
+    pub _spare3: [u64; 12],
+}
+
+// Checked against `include/uapi/linux/stat.h` of Linux.
+const _: () = {
+    use core::mem::{offset_of, size_of};
+    assert!(size_of::<StatxTimestamp>() == 16);
+    assert!(size_of::<StatX>() == 256);
+    assert!(offset_of!(StatX, stx_mode) == 28);
+    assert!(offset_of!(StatX, stx_ino) == 32);
+    assert!(offset_of!(StatX, stx_size) == 40);
+    assert!(offset_of!(StatX, stx_blocks) == 48);
+    assert!(offset_of!(StatX, stx_atime) == 64);
+    assert!(offset_of!(StatX, stx_btime) == 80);
+    assert!(offset_of!(StatX, stx_ctime) == 96);
+    assert!(offset_of!(StatX, stx_mtime) == 112);
+    assert!(offset_of!(StatX, stx_rdev_major) == 128);
+    assert!(offset_of!(StatX, stx_mnt_id) == 144);
+};
+
+bitflags! {
+    /// Fields requested from / reported by `statx`.
+    #[derive(Debug, Clone, Copy)]
+    pub struct StatxMask: u32 {
+        /// stx_mode & S_IFMT
+        const STATX_TYPE = 0x0001;
+        /// stx_mode & ~S_IFMT
+        const STATX_MODE = 0x0002;
+        /// stx_nlink
+        const STATX_NLINK = 0x0004;
+        /// stx_uid
+        const STATX_UID = 0x0008;
+        /// stx_gid
+        const STATX_GID = 0x0010;
+        /// stx_atime
+        const STATX_ATIME = 0x0020;
+        /// stx_mtime
+        const STATX_MTIME = 0x0040;
+        /// stx_ctime
+        const STATX_CTIME = 0x0080;
+        /// stx_ino
+        const STATX_INO = 0x0100;
+        /// stx_size
+        const STATX_SIZE = 0x0200;
+        /// stx_blocks
+        const STATX_BLOCKS = 0x0400;
+        /// All of the above
+        const STATX_BASIC_STATS = 0x07ff;
+    }
+}
+
+/// `struct rlimit` (as well as `struct rlimit64`) of Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct RLimit {
+    /// The soft limit
+    pub rlim_cur: u64,
+    /// The hard limit, the ceiling of the soft limit
+    pub rlim_max: u64,
+}
+
+impl RLimit {
+    /// Both the soft and the hard limits are unlimited.
+    pub const INFINITY: Self = Self::new(RLIM_INFINITY, RLIM_INFINITY);
+
+    pub const fn new(rlim_cur: u64, rlim_max: u64) -> Self {
+        Self { rlim_cur, rlim_max }
+    }
+}
+
+/// The value of an unlimited resource.
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// The number of kinds of resources.
+pub const RLIM_NLIMITS: usize = 16;
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(u32)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Clone, Copy)]
+    /// The resources that can be limited, `RLIMIT_*` of Linux.
+    pub enum RLimitResource {
+        /// CPU time in seconds
+        CPU = 0,
+        /// Maximum size of files created
+        FSIZE = 1,
+        /// Maximum size of the data segment
+        DATA = 2,
+        /// Maximum size of the stack
+        STACK = 3,
+        /// Maximum size of core files
+        CORE = 4,
+        /// Maximum resident set size
+        RSS = 5,
+        /// Maximum number of processes
+        NPROC = 6,
+        /// Maximum number of open files, i.e., one greater than the largest file
+        /// descriptor that can be opened
+        NOFILE = 7,
+        /// Maximum locked-in-memory address space
+        MEMLOCK = 8,
+        /// Maximum size of the address space
+        AS = 9,
+        /// Maximum number of file locks
+        LOCKS = 10,
+        /// Maximum number of pending signals
+        SIGPENDING = 11,
+        /// Maximum bytes in POSIX message queues
+        MSGQUEUE = 12,
+        /// Ceiling of the nice value
+        NICE = 13,
+        /// Ceiling of the real-time priority
+        RTPRIO = 14,
+        /// Timeout for real-time tasks in microseconds
+        RTTIME = 15,
+    }
+}
+
+/// `struct pollfd` of Linux.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PollFd {
+    /// The file descriptor, ignored if negative
+    pub fd: i32,
+    /// The requested events
+    pub events: i16,
+    /// The returned events
+    pub revents: i16,
+}
+
+bitflags! {
+    /// The events of [`PollFd`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct PollEvents: i16 {
+        /// There is data to read.
+        const POLLIN = 0x0001;
+        /// There is urgent data to read.
+        const POLLPRI = 0x0002;
+        /// Writing is now possible.
+        const POLLOUT = 0x0004;
+        /// Error condition (only returned).
+        const POLLERR = 0x0008;
+        /// Hang up (only returned).
+        const POLLHUP = 0x0010;
+        /// Invalid request: fd not open (only returned).
+        const POLLNVAL = 0x0020;
+        /// Equivalent to `POLLIN`.
+        const POLLRDNORM = 0x0040;
+        /// Equivalent to `POLLOUT`.
+        const POLLWRNORM = 0x0100;
+    }
+}
+
+/// The number of control characters in [`Termios`].
+pub const NCCS: usize = 19;
+
+/// The terminal attributes of `TCGETS` and `TCSETS`, i.e., the kernel
+/// `struct termios` without the speed fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Termios {
+    /// Input modes
+    pub c_iflag: u32,
+    /// Output modes
+    pub c_oflag: u32,
+    /// Control modes
+    pub c_cflag: u32,
+    /// Local modes
+    pub c_lflag: u32,
+    /// Line discipline
+    pub c_line: u8,
+    /// Control characters
+    pub c_cc: [u8; NCCS],
+}
+
+/// The window size of `TIOCGWINSZ` and `TIOCSWINSZ`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WinSize {
+    /// Rows, in characters
+    pub ws_row: u16,
+    /// Columns, in characters
+    pub ws_col: u16,
+    /// Width, in pixels (unused)
+    pub ws_xpixel: u16,
+    /// Height, in pixels (unused)
+    pub ws_ypixel: u16,
+}
+
+// Checked against `include/uapi/asm-generic/termbits.h` of Linux.
+const _: () = {
+    use core::mem::{offset_of, size_of};
+    assert!(size_of::<Termios>() == 36);
+    assert!(offset_of!(Termios, c_line) == 16);
+    assert!(offset_of!(Termios, c_cc) == 17);
+    assert!(size_of::<WinSize>() == 8);
+};
@@ -1,6 +1,9 @@
 #![no_std]
 #![no_main]
 #![doc = include_str!("../README.md")]
+// For the bitmap of `FlattenObjects`, sized from its capacity.
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
 
 #[macro_use]
 extern crate log;
@@ -10,45 +13,141 @@ extern crate axstd;
 mod ctypes;
 
 mod mm;
+mod pid;
 mod syscall_imp;
 mod task;
-use alloc::{string::ToString, sync::Arc, vec, vec::Vec};
+mod testcase;
+mod tty;
+use alloc::{sync::Arc, vec::Vec};
+use core::time::Duration;
 
-use axhal::arch::UspaceContext;
+use axhal::{arch::UspaceContext, time::monotonic_time};
 use axstd::println;
 use axsync::Mutex;
+use axtask::{AxTaskRef, TaskExtRef};
 use memory_addr::VirtAddr;
+use testcase::{Status, TestResult, Testcase};
+
+/// How often the harness checks whether a testcase has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long the harness waits for a killed testcase to exit.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Wait for `task` to exit for at most `timeout`, and return its exit code, or
+/// `None` if it's still running.
+fn wait_timeout(task: &AxTaskRef, timeout: Duration) -> Option<i32> {
+    let deadline = monotonic_time() + timeout;
+    while task.state() != axtask::TaskState::Exited {
+        if monotonic_time() >= deadline {
+            return None;
+        }
+        axtask::sleep(POLL_INTERVAL);
+    }
+    Some(task.exit_code())
+}
+
+/// Load and run `testcase`, killing it with all the processes it spawned if it
+/// runs out of time.
+fn run_testcase(testcase: &Testcase) -> TestResult {
+    let start = monotonic_time();
+    let result = |status, code| TestResult {
+        name: testcase.name().into(),
+        status,
+        expect: testcase.expect,
+        code,
+        time: monotonic_time() - start,
+    };
+
+    let mut uspace = axmm::new_user_aspace(
+        VirtAddr::from_usize(axconfig::plat::USER_SPACE_BASE),
+        axconfig::plat::USER_SPACE_SIZE,
+    )
+    .expect("Failed to create user address space");
+    let (entry_vaddr, ustack_top, heap_bottom) =
+        match mm::load_user_app(&mut testcase.args.clone().into(), &mut uspace) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                error!("Failed to load {}: {:?}", testcase.path, err);
+                return result(Status::Fail, -1);
+            }
+        };
+    println!("Loading complete");
+    let _ = axfs::api::set_current_dir(testcase.dir());
+    info!("dir: {:?}", testcase.dir());
+    let attrs = task::TaskAttrs {
+        affinity: testcase.affinity,
+        ..Default::default()
+    };
+    let user_task = match task::spawn_user_task_with_attrs(
+        Arc::new(Mutex::new(uspace)),
+        UspaceContext::new(entry_vaddr.into(), ustack_top, 2333),
+        heap_bottom.as_usize() as u64,
+        attrs,
+    ) {
+        Ok(task) => task,
+        Err(err) => {
+            error!("Failed to spawn {}: {:?}", testcase.path, err);
+            return result(Status::Fail, -1);
+        }
+    };
+    let faults = mm::demand_page_faults();
+    let user_pid = user_task.task_ext().proc_id;
+    let (status, exit_code) = match wait_timeout(&user_task, testcase.timeout) {
+        Some(0) => (Status::Pass, Some(0)),
+        Some(code) => (Status::Fail, Some(code)),
+        None => {
+            warn!(
+                "User task {} timed out after {:?}, killing it",
+                testcase.path, testcase.timeout
+            );
+            // The testcase is the leader of a new session, which contains all
+            // the processes it spawned.
+            task::kill_session(user_pid);
+            (Status::Timeout, wait_timeout(&user_task, KILL_GRACE_PERIOD))
+        }
+    };
+    match exit_code {
+        Some(_) => pid::free_pid(user_pid),
+        // The task may still access its PID, so leave it allocated.
+        None => warn!("User task {} is not exiting after killed", testcase.path),
+    }
+    task::reap_orphans();
+    info!(
+        "User task {} exited with code: {:?}",
+        testcase.path, exit_code
+    );
+    info!(
+        "User task {} triggered {} demand paging faults",
+        testcase.path,
+        mm::demand_page_faults() - faults
+    );
+    result(status, exit_code.unwrap_or(task::KILLED_EXIT_CODE))
+}
 
 #[unsafe(no_mangle)]
 fn main() {
-    let testcases = option_env!("AX_TESTCASES_LIST")
-        .unwrap_or_else(|| "Please specify the testcases list by making user_apps")
-        .split(',')
-        .filter(|&x| !x.is_empty());
+    tty::init();
+    let testcases = testcase::load_testcases();
+    let mut results = Vec::with_capacity(testcases.len());
     println!("#### OS COMP TEST GROUP START basic-musl ####");
     for testcase in testcases {
-        println!("Testing {}: ", testcase.split('/').next_back().unwrap());
-
-        let args = vec![testcase.to_string()];
-        let path = testcase.split('/').collect::<Vec<&str>>();
-        // 获取除最后一个元素外的所有元素，并用 '/' 连接
-        let joined = path.iter().take(path.len() - 1).map(|s| *s).collect::<Vec<&str>>().join("/");
-        let mut uspace = axmm::new_user_aspace(
-            VirtAddr::from_usize(axconfig::plat::USER_SPACE_BASE),
-            axconfig::plat::USER_SPACE_SIZE,
-        )
-        .expect("Failed to create user address space");
-        let (entry_vaddr, ustack_top) = mm::load_user_app(&mut (args.into()), &mut uspace).unwrap();
-        println!("Loading complete");
-        let _ = axfs::api::set_current_dir(joined.as_str());
-        info!("dir: {:?}", joined);
-        let user_task = task::spawn_user_task(
-            Arc::new(Mutex::new(uspace)),
-            UspaceContext::new(entry_vaddr.into(), ustack_top, 2333),
-            0,
-        );
-        let exit_code = user_task.join();
-        info!("User task {} exited with code: {:?}", testcase, exit_code);
+        println!("Testing {}: ", testcase.name());
+        let result = run_testcase(&testcase);
+        result.report();
+        results.push(result);
     }
     println!("#### OS COMP TEST GROUP END basic-musl ####");
+    testcase::print_summary(&results);
+
+    #[cfg(feature = "syscall_stats")]
+    for (sysno, count) in syscall_imp::syscall_counts() {
+        println!("syscall {:?}: {} calls", sysno, count);
+    }
+
+    // QEMU exits with a nonzero status on failures, except on loongarch64,
+    // which can't report it, so check the summary there.
+    if !results.iter().all(TestResult::is_expected) {
+        axhal::misc::terminate_with_failure();
+    }
 }
@@ -1,44 +1,192 @@
-use core::str::from_utf8;
+use core::{
+    str::from_utf8,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
 
-use alloc::{collections::vec_deque::VecDeque, string::String, vec};
+use alloc::{collections::vec_deque::VecDeque, string::String, sync::Arc, vec, vec::Vec};
 
 use axerrno::{AxError, AxResult};
+use axfs::fops::{File, OpenOptions};
 use axhal::{
     paging::MappingFlags,
+    time::{monotonic_time, monotonic_time_nanos},
     trap::{PAGE_FAULT, register_trap_handler},
 };
 
-use axmm::AddrSpace;
+use axmm::{AddrSpace, FileBacking, FileRegion};
+use axstd::println;
 use axtask::TaskExtRef;
-use kernel_elf_parser::{AuxvEntry, ELFParser, app_stack_region};
-use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr};
-use xmas_elf::{ElfFile, program::SegmentData};
+use kernel_elf_parser::{
+    AuxvEntry, AuxvOptions, ELFPH, ELFParser, EntryAudit, StackLayout, StackOptions,
+    app_stack_region_with_options,
+};
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr, VirtAddrRange};
+use xmas_elf::ElfFile;
+
+use crate::ctypes::RLimitResource;
+
+/// The maximum size of the user heap managed by `brk`.
+pub const USER_HEAP_SIZE: usize = 0x20000;
+
+/// The size of the guard region below the lowest address the user stack can
+/// grow to.
+///
+/// The guard region is reserved but never mapped, so that a stack overflow
+/// faults there instead of running into other mappings.
+pub const USER_STACK_GUARD_SIZE: usize = 0x10000;
+
+/// The number of page faults resolved by allocating frames on demand.
+static DEMAND_PAGE_FAULTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of page faults resolved by demand paging so far.
+pub fn demand_page_faults() -> usize {
+    DEMAND_PAGE_FAULTS.load(Ordering::Relaxed)
+}
+
+/// An executable file of user apps, which the segments are read from on
+/// demand.
+struct ExecFile(File);
+
+impl FileBacking for ExecFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        self.0.read_at(offset, buf)
+    }
+}
+
+impl ExecFile {
+    fn open(path: &str) -> AxResult<Arc<Self>> {
+        let mut opts = OpenOptions::new();
+        opts.read(true);
+        Ok(Arc::new(Self(File::open(path, &opts)?)))
+    }
+
+    fn size(&self) -> AxResult<usize> {
+        Ok(self.0.get_attr()?.size() as usize)
+    }
+
+    /// Read `len` bytes at `offset` of the file, failing with
+    /// [`AxError::InvalidData`] if the file is too short.
+    fn read_exact_at(&self, offset: usize, len: usize) -> AxResult<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut read = 0;
+        while read < len {
+            match self.0.read_at((offset + read) as u64, &mut buf[read..])? {
+                0 => return Err(AxError::InvalidData),
+                n => read += n,
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Read the ELF header and the program headers, which are all that is
+    /// needed to parse the file and map the segments.
+    ///
+    /// The program header table must be in the file and of a sane size, so
+    /// that a malformed header can't make the kernel allocate a huge buffer.
+    fn read_headers(&self) -> AxResult<Vec<u8>> {
+        const EHDR_SIZE: usize = 64;
+        /// The size of a 64-bit program header, the largest one.
+        const MAX_PHENT_SIZE: usize = 56;
+        /// The size limit of the program header table, as in Linux.
+        const MAX_PH_SIZE: usize = 64 * 1024;
+
+        let ehdr = self.read_exact_at(0, EHDR_SIZE)?;
+        let header = xmas_elf::header::parse_header(&ehdr).map_err(|_| AxError::InvalidData)?;
+        let ph_entry_size = header.pt2.ph_entry_size() as usize;
+        let ph_size = header.pt2.ph_count() as usize * ph_entry_size;
+        if ph_entry_size > MAX_PHENT_SIZE || ph_size > MAX_PH_SIZE {
+            return Err(AxError::InvalidData);
+        }
+        let file_size = self.size()?;
+        let ph_end = usize::try_from(header.pt2.ph_offset())
+            .ok()
+            .and_then(|offset| offset.checked_add(ph_size))
+            .filter(|&end| end <= file_size)
+            .ok_or(AxError::InvalidData)?;
+        self.read_exact_at(0, ph_end.max(EHDR_SIZE))
+    }
+}
+
+/// Map a LOAD segment of `file` to the user address space.
+///
+/// The pages filled from the file are read on demand, except that the last
+/// page of a writable segment, which is partially filled from the file, is
+/// copied now. The zero-filled rest of the segment (`.bss`) is allocated on
+/// demand.
+fn map_segment(file: &Arc<ExecFile>, segment: &ELFPH, uspace: &mut AddrSpace) -> AxResult {
+    let seg_pad = segment.file_copy_dest_offset(PAGE_SIZE_4K);
+    if seg_pad != segment.offset % PAGE_SIZE_4K
+        || segment.filesz > segment.memsz
+        || segment.file_range.1 > file.size()?
+    {
+        return Err(AxError::InvalidData);
+    }
+
+    let (seg_start, seg_len) = segment.map_range(PAGE_SIZE_4K);
+    let seg_end = seg_start + seg_len;
+    let file_end = segment.zero_start;
+    // A read-only page is never written, so the zeros after the file data in
+    // the last page can be filled on demand too.
+    let writable = segment.flags.contains(MappingFlags::WRITE);
+    let lazy_end = if writable {
+        file_end.align_down_4k().max(seg_start)
+    } else {
+        file_end.align_up_4k()
+    };
+
+    if lazy_end > seg_start {
+        let region = FileRegion::new(
+            file.clone(),
+            seg_start,
+            (segment.offset - seg_pad) as u64,
+            file_end - seg_start,
+        );
+        uspace.map_file(seg_start, lazy_end - seg_start, segment.flags, region)?;
+    }
+    if seg_end > lazy_end {
+        uspace.map_alloc(lazy_end, seg_end - lazy_end, segment.flags, false)?;
+    }
+    if file_end > lazy_end {
+        let copy_start = segment.vaddr.max(lazy_end);
+        let data = file.read_exact_at(
+            segment.offset + (copy_start - segment.vaddr),
+            file_end - copy_start,
+        )?;
+        uspace.alloc_for_lazy(copy_start, data.len())?;
+        uspace.write(copy_start, &data)?;
+        if segment.flags.contains(MappingFlags::EXECUTE) {
+            axhal::arch::flush_icache_all();
+        }
+    }
+    Ok(())
+}
 
 /// Map the elf file to the user address space.
 ///
 /// # Arguments
 /// - `args`: The arguments of the user app. The first argument is the path of the user app.
+/// - `file`: The elf file.
 /// - `elf_parser`: The parser of the elf file.
 /// - `uspace`: The address space of the user app.
 ///
 /// # Returns
 /// - The entry point of the user app.
+/// - The auxiliary vector of the user app.
+/// - The end of the mapped segments, which is where the heap starts.
+/// - The program headers to copy onto the user stack if they are not mapped.
 fn map_elf(
     args: &mut VecDeque<String>,
+    file: &Arc<ExecFile>,
     elf_parser: &ELFParser,
     uspace: &mut AddrSpace,
-) -> AxResult<(VirtAddr, [AuxvEntry; 17])> {
+) -> AxResult<(VirtAddr, [AuxvEntry; 19], VirtAddr, Option<Vec<u8>>)> {
     let elf = elf_parser.elf();
     if let Some(interp) = elf
         .program_iter()
         .find(|ph| ph.get_type() == Ok(xmas_elf::program::Type::Interp))
     {
-        let interp = match interp.get_data(elf) {
-            Ok(SegmentData::Undefined(data)) => data,
-            _ => panic!("Invalid data in Interp Elf Program Header"),
-        };
-
-        let interp_path = from_utf8(interp).map_err(|_| AxError::InvalidInput)?;
+        let interp = file.read_exact_at(interp.offset() as usize, interp.file_size() as usize)?;
+        let interp_path = from_utf8(&interp).map_err(|_| AxError::InvalidInput)?;
         // remove trailing '\0'
         let mut real_interp_path =
             axfs::api::canonicalize(interp_path.trim_matches(char::from(0)))?;
@@ -49,53 +197,113 @@ fn map_elf(
             real_interp_path = String::from("/musl/lib/libc.so");
         }
 
-        let interp_data = axfs::api::read(real_interp_path.as_str())?;
-        let interp_elf = ElfFile::new(&interp_data).map_err(|_| AxError::InvalidData)?;
+        let interp_file = ExecFile::open(real_interp_path.as_str())?;
+        let interp_headers = interp_file.read_headers()?;
+        let interp_elf = ElfFile::new(&interp_headers).map_err(|_| AxError::InvalidData)?;
         let uspace_base = uspace.base().as_usize();
 
-        let interp_elf_parser = ELFParser::new(
+        let interp_elf_parser = ELFParser::new_with_layout(
             &interp_elf,
             axconfig::plat::USER_INTERP_BASE,
             Some(uspace_base as isize),
             uspace_base,
+            uspace.size(),
         )
         .map_err(|_| AxError::InvalidData)?;
         // Set the first argument to the path of the user app.
         args.push_front(real_interp_path);
-        return map_elf(args, &interp_elf_parser, uspace);
+        return map_elf(args, &interp_file, &interp_elf_parser, uspace);
     }
-    for segement in elf_parser.ph_load() {
+    elf_parser
+        .validate_load_segments(uspace.base().as_usize(), uspace.size())
+        .map_err(|err| {
+            warn!("{}: {}", args[0], err);
+            AxError::InvalidData
+        })?;
+    audit_elf(&args[0], elf_parser);
+    let mut segments_end = VirtAddr::from_usize(0);
+    for segement in elf_parser.ph_load_iter() {
         debug!(
             "Mapping ELF segment: [{:#x?}, {:#x?}) flags: {:#x?}",
             segement.vaddr,
             segement.vaddr + segement.memsz as usize,
             segement.flags
         );
-        let seg_pad = segement.vaddr.align_offset_4k();
-        assert_eq!(seg_pad, segement.offset % PAGE_SIZE_4K);
-
-        let seg_align_size =
-            (segement.memsz as usize + seg_pad + PAGE_SIZE_4K - 1) & !(PAGE_SIZE_4K - 1);
-        uspace.map_alloc(
-            segement.vaddr.align_down_4k(),
-            seg_align_size,
-            segement.flags,
-            true,
-        )?;
-        let seg_data = elf
-            .input
-            .get(segement.offset..segement.offset + segement.filesz as usize)
-            .ok_or(AxError::InvalidData)?;
-        uspace.write(segement.vaddr, seg_data)?;
-        // TDOO: flush the I-cache
+        map_segment(file, &segement, uspace)?;
+        segments_end = segments_end.max((segement.vaddr + segement.memsz as usize).align_up_4k());
     }
 
+    let phdr_copy = match elf_parser.phdr() {
+        Ok(_) => None,
+        Err(err) => {
+            debug!("{}: {}, copying them onto the stack", args[0], err);
+            Some(elf_parser.phdr_table_bytes().to_vec())
+        }
+    };
     Ok((
         elf_parser.entry().into(),
-        elf_parser.auxv_vector(PAGE_SIZE_4K),
+        elf_parser.auxv_vector_with_options(PAGE_SIZE_4K, &AuxvOptions::default()),
+        segments_end,
+        phdr_copy,
     ))
 }
 
+/// Warn about the risky permissions of the segments of the ELF file, which
+/// are still mapped as they ask for.
+fn audit_elf(path: &str, elf_parser: &ELFParser) {
+    let audit = elf_parser.audit();
+    if let Some(i) = audit.wx_segment {
+        warn!("{}: LOAD segment {} is writable and executable", path, i);
+    }
+    match audit.entry {
+        EntryAudit::Executable(_) => {}
+        EntryAudit::NotExecutable(i) => {
+            warn!(
+                "{}: the entry point is in LOAD segment {}, which is not executable",
+                path, i
+            );
+        }
+        EntryAudit::Unmapped => warn!("{}: the entry point is not in any LOAD segment", path),
+    }
+    if let Some(relro) = audit.relro {
+        debug!("{}: RELRO range {:?}", path, relro);
+    }
+}
+
+/// The platform string for `AT_PLATFORM`, which is what `uname -m` reports.
+const PLATFORM: &str = if cfg!(target_arch = "x86_64") {
+    "x86_64"
+} else if cfg!(target_arch = "aarch64") {
+    "aarch64"
+} else if cfg!(target_arch = "riscv64") {
+    "riscv64"
+} else {
+    "loongarch64"
+};
+
+/// Generate the 16 bytes pointed by `AT_RANDOM`, which libc uses for the stack
+/// protector canary and pointer guard.
+///
+/// There is no entropy source yet, so they are derived from the current time
+/// and a global counter with SplitMix64, which at least makes them differ
+/// between processes.
+fn at_random_bytes() -> [u8; 16] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut state =
+        monotonic_time_nanos() ^ COUNTER.fetch_add(1, Ordering::Relaxed).rotate_left(32);
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&next().to_le_bytes());
+    bytes[8..].copy_from_slice(&next().to_le_bytes());
+    bytes
+}
+
 /// Load the user app to the user address space.
 ///
 /// # Arguments
@@ -105,26 +313,47 @@ fn map_elf(
 /// # Returns
 /// - The entry point of the user app.
 /// - The stack pointer of the user app.
+/// - The bottom of the user heap.
 pub fn load_user_app(
     args: &mut VecDeque<String>,
     uspace: &mut AddrSpace,
-) -> AxResult<(VirtAddr, VirtAddr)> {
+) -> AxResult<(VirtAddr, VirtAddr, VirtAddr)> {
     if args.is_empty() {
         return Err(AxError::InvalidInput);
     }
-    let file_data = axfs::api::read(args[0].as_str())?;
-    let elf = ElfFile::new(&file_data).map_err(|_| AxError::InvalidData)?;
+    let load_start = monotonic_time();
+    // `map_elf` puts the interpreter before it, but AT_EXECFN still names the
+    // program.
+    let execfn = args[0].clone();
+    // Only the headers are read now, the segments are read on demand.
+    let file = ExecFile::open(args[0].as_str())?;
+    let headers = file.read_headers()?;
+    let elf = ElfFile::new(&headers).map_err(|_| AxError::InvalidData)?;
 
     let uspace_base = uspace.base().as_usize();
-    let elf_parser = ELFParser::new(
+    let elf_parser = ELFParser::new_with_layout(
         &elf,
         axconfig::plat::USER_INTERP_BASE,
         Some(uspace_base as isize),
         uspace_base,
+        uspace.size(),
     )
     .map_err(|_| AxError::InvalidData)?;
+    // Fail early rather than jumping to a garbage entry point.
+    elf_parser.validate_native().map_err(|err| {
+        warn!("{}: {}", args[0], err);
+        AxError::InvalidData
+    })?;
+
+    let (entry, mut auxv, heap_bottom, phdr_copy) = map_elf(args, &file, &elf_parser, uspace)?;
+    // The heap is populated on demand, `brk` only moves the heap top inside it.
+    uspace.map_alloc(
+        heap_bottom,
+        USER_HEAP_SIZE,
+        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+        false,
+    )?;
 
-    let (entry, mut auxv) = map_elf(args, &elf_parser, uspace)?;
     // The user stack is divided into two parts:
     // `ustack_start` -> `ustack_pointer`: It is the stack space that users actually read and write.
     // `ustack_pointer` -> `ustack_end`: It is the space that contains the arguments, environment variables and auxv passed to the app.
@@ -149,45 +378,149 @@ pub fn load_user_app(
         "LD_DEBUG=files".into(),
     ];
 
-    let stack_data = app_stack_region(
+    let layout = StackLayout::from_class(elf_parser.class()).ok_or(AxError::InvalidData)?;
+    let stack_opts = StackOptions {
+        layout,
+        platform: Some(PLATFORM),
+        execfn: Some(&execfn),
+        phdr: phdr_copy.as_deref(),
+    };
+    let stack = app_stack_region_with_options(
         args.make_contiguous(),
         &env,
         &mut auxv,
+        at_random_bytes(),
         ustack_start,
         ustack_size,
-    );
+        &stack_opts,
+    )
+    .map_err(|err| {
+        warn!("{}: {}", args[0], err);
+        AxError::InvalidInput
+    })?;
+    // The stack is populated on demand, and grows downward on page faults below it,
+    // see `grow_user_stack`. It's executable only if the app asks for it.
+    uspace.map_alloc(ustack_start, ustack_size, elf_parser.stack_flags(), false)?;
+
+    // Reserve the guard region with no access permission, so that nothing else
+    // can be mapped there.
+    let guard_end = ustack_end - axconfig::plat::USER_STACK_MAX_SIZE;
     uspace.map_alloc(
-        ustack_start,
-        ustack_size,
-        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
-        true,
+        guard_end - USER_STACK_GUARD_SIZE,
+        USER_STACK_GUARD_SIZE,
+        MappingFlags::empty(),
+        false,
     )?;
 
-    let user_sp = ustack_end - stack_data.len();
+    let user_sp = stack.sp;
+
+    // Only the pages holding the initial stack content are populated now.
+    uspace.alloc_for_lazy(user_sp, stack.data.len())?;
+    uspace.write(user_sp, stack.data.as_slice())?;
+
+    info!("Loaded {} in {:?}", args[0], monotonic_time() - load_start);
+    Ok((entry, user_sp, heap_bottom))
+}
+
+/// Returns the range that the user stack can grow in, i.e. from
+/// `USER_STACK_TOP - max_size` to `USER_STACK_TOP`, where `max_size` is capped
+/// by `USER_STACK_MAX_SIZE`.
+fn user_stack_range(max_size: usize) -> VirtAddrRange {
+    let ustack_end = VirtAddr::from_usize(axconfig::plat::USER_STACK_TOP);
+    let ustack_limit = ustack_end - max_size.min(axconfig::plat::USER_STACK_MAX_SIZE);
+    VirtAddrRange::new(ustack_limit, ustack_end)
+}
+
+/// Returns whether a fault on `vaddr` is caused by a stack overflow, i.e. it
+/// is below the range that the user stack can grow in, but not below the guard
+/// region.
+fn is_stack_overflow(vaddr: VirtAddr, ustack_range: VirtAddrRange) -> bool {
+    let guard_end =
+        VirtAddr::from_usize(axconfig::plat::USER_STACK_TOP - axconfig::plat::USER_STACK_MAX_SIZE);
+    vaddr >= guard_end - USER_STACK_GUARD_SIZE && vaddr < ustack_range.start
+}
+
+/// Grow the user stack downward so that it covers `vaddr`.
+///
+/// The stack can only grow in `ustack_range`, so it never reaches the guard
+/// region below. The new part is populated on demand like the rest of the
+/// stack.
+///
+/// Returns `false` if `vaddr` is out of the range where the stack can grow.
+fn grow_user_stack(uspace: &mut AddrSpace, vaddr: VirtAddr, ustack_range: VirtAddrRange) -> bool {
+    if !ustack_range.contains(vaddr) {
+        return false;
+    }
 
-    uspace.write(user_sp, stack_data.as_slice())?;
+    // The stack may consist of several areas after growing.
+    let mut ustack_start = ustack_range.end;
+    while ustack_start > ustack_range.start {
+        match uspace.find_area_range(ustack_start - 1) {
+            Some(area) => ustack_start = area.start,
+            None => break,
+        }
+    }
+    if vaddr >= ustack_start {
+        return false;
+    }
 
-    Ok((entry, user_sp))
+    let grow_start = vaddr.align_down_4k();
+    debug!(
+        "Growing user stack: {:#x?} -> {:#x?}",
+        ustack_start, grow_start
+    );
+    // The new part has the same permissions as the rest of the stack.
+    let flags = uspace
+        .find_area_flags(ustack_range.end - 1)
+        .unwrap_or(MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER);
+    uspace
+        .map_alloc(grow_start, ustack_start - grow_start, flags, false)
+        .is_ok()
 }
 
 #[register_trap_handler(PAGE_FAULT)]
 fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+    let curr = axtask::current();
+    // Kernel tasks have no user address space.
+    if unsafe { curr.task_ext_ptr() }.is_null() {
+        return false;
+    }
+
+    let stack_limit = curr.task_ext().get_rlimit(RLimitResource::STACK).rlim_cur;
+    let ustack_range = user_stack_range(stack_limit.try_into().unwrap_or(usize::MAX));
+    let mut aspace = curr.task_ext().aspace.lock();
+    // The kernel may touch user memory which has not been populated yet, e.g.
+    // the buffer of `read`, but a fault on a kernel address is a real bug.
+    if !is_user && !aspace.contains_range(vaddr, 1) {
+        return false;
+    }
+    let handled = aspace.handle_page_fault(vaddr, access_flags)
+        || (grow_user_stack(&mut aspace, vaddr, ustack_range)
+            && aspace.handle_page_fault(vaddr, access_flags));
+    drop(aspace);
+
+    if handled {
+        DEMAND_PAGE_FAULTS.fetch_add(1, Ordering::Relaxed);
+        return true;
+    }
+    if is_user && is_stack_overflow(vaddr, ustack_range) {
+        // TODO: send SIGSEGV when signals are supported.
+        println!(
+            "{}: stack overflow at {:#x}, the stack is [{:#x}, {:#x}), exit!",
+            curr.id_name(),
+            vaddr,
+            ustack_range.start,
+            ustack_range.end
+        );
+        crate::task::exit_current(-1);
+    }
     if is_user {
-        if !axtask::current()
-            .task_ext()
-            .aspace
-            .lock()
-            .handle_page_fault(vaddr, access_flags)
-        {
-            warn!(
-                "{}: segmentation fault at {:#x}, exit!",
-                axtask::current().id_name(),
-                vaddr
-            );
-            axtask::exit(-1);
-        }
-        true
-    } else {
-        false
+        warn!(
+            "{}: segmentation fault at {:#x}, exit!",
+            curr.id_name(),
+            vaddr
+        );
+        crate::task::exit_current(-1);
     }
+    false
 }
@@ -0,0 +1,75 @@
+//! The PID table, mapping process IDs to the tasks.
+//!
+//! PIDs are allocated cyclically: a new PID is the first free one after the
+//! last allocated PID, wrapping around at [`PID_MAX`]. So a PID is not reused
+//! soon after it's freed, and a stale PID kept by the user (e.g., for `kill`)
+//! is unlikely to refer to an unrelated process.
+//!
+//! A PID stays in the table until the process is reaped by its parent with
+//! `wait4`, so an exited process can still be looked up.
+
+use alloc::vec::Vec;
+use axsync::Mutex;
+use axtask::AxTaskRef;
+use flatten_objects::FlattenObjects;
+
+/// The PID of the init process, i.e., the kernel itself.
+///
+/// It's the parent of the tasks spawned by the kernel, and adopts the orphans.
+pub const INIT_PID: usize = 1;
+
+/// The upper bound (exclusive) of PIDs.
+pub const PID_MAX: usize = 1024;
+
+struct PidTable {
+    /// The tasks indexed by PIDs, `None` if the PID is allocated but the task
+    /// is not spawned yet.
+    tasks: FlattenObjects<Option<AxTaskRef>, PID_MAX>,
+    /// The last allocated PID.
+    last_pid: usize,
+}
+
+static PID_TABLE: Mutex<PidTable> = Mutex::new(PidTable {
+    tasks: FlattenObjects::new(),
+    last_pid: INIT_PID,
+});
+
+/// Allocate a PID for a task to be spawned, or return `None` if all PIDs are
+/// in use.
+///
+/// The task must be registered with [`register_task`] once it's spawned.
+pub fn alloc_pid() -> Option<usize> {
+    let mut table = PID_TABLE.lock();
+    let last_pid = table.last_pid;
+    let pid = (last_pid + 1..PID_MAX)
+        .chain(INIT_PID + 1..=last_pid)
+        .find(|&pid| !table.tasks.is_assigned(pid))?;
+    table.tasks.add_at(pid, None).ok()?;
+    table.last_pid = pid;
+    Some(pid)
+}
+
+/// Register the spawned `task` to its PID allocated by [`alloc_pid`].
+pub fn register_task(pid: usize, task: &AxTaskRef) {
+    if let Some(slot) = PID_TABLE.lock().tasks.get_mut(pid) {
+        *slot = Some(task.clone());
+    }
+}
+
+/// Free `pid` after the process is reaped.
+pub fn free_pid(pid: usize) {
+    PID_TABLE.lock().tasks.remove(pid);
+}
+
+/// Find the task of `pid`.
+pub fn find_task(pid: usize) -> Option<AxTaskRef> {
+    PID_TABLE.lock().tasks.get(pid).cloned().flatten()
+}
+
+/// Get all the tasks in the table, including the exited but unreaped ones.
+pub fn all_tasks() -> Vec<AxTaskRef> {
+    let table = PID_TABLE.lock();
+    (0..PID_MAX)
+        .filter_map(|pid| table.tasks.get(pid).cloned().flatten())
+        .collect()
+}
@@ -1,11 +1,15 @@
-use core::ffi::{c_char, c_int, c_void};
+use core::ffi::{c_char, c_void};
 
 use alloc::string::ToString;
-use arceos_posix_api::AT_FDCWD;
-use axerrno::{AxError, LinuxError};
+use arceos_posix_api::{FilePath, HARDLINK_MANAGER};
+use axerrno::{LinuxError, LinuxResult};
 use axtask::{TaskExtRef, current};
 
-use crate::syscall_body;
+use super::path::user_path_at;
+use crate::syscall_imp::posix_result;
+
+/// Remove a directory instead of a file in `unlinkat`.
+pub(crate) const AT_REMOVEDIR: u32 = 0x200;
 
 /// The ioctl() system call manipulates the underlying device parameters
 /// of special files.
@@ -15,54 +19,32 @@ use crate::syscall_body;
 /// * `op` - The request code. It is of type unsigned long in glibc and BSD,
 ///   and of type int in musl and other UNIX systems.
 /// * `argp` - The argument to the request. It is a pointer to a memory location
-pub(crate) fn sys_ioctl(_fd: i32, _op: usize, _argp: *mut c_void) -> i32 {
-    syscall_body!(sys_ioctl, {
-        warn!("Unimplemented syscall: SYS_IOCTL");
-        Ok(0)
-    })
+///
+/// The request is handled by the file itself, and fails with `ENOTTY` if the
+/// file doesn't support it.
+pub(crate) fn sys_ioctl(fd: i32, op: usize, argp: *mut c_void) -> LinuxResult<usize> {
+    arceos_posix_api::get_file_like(fd)?.ioctl(op as u32, argp as usize)
 }
 
-pub(crate) fn sys_chdir(path: *const c_char) -> c_int {
-    let path = match arceos_posix_api::char_ptr_to_str(path) {
-        Ok(path) => path,
-        Err(err) => {
-            warn!("Failed to convert path: {err:?}");
-            return -1;
-        }
-    };
+pub(crate) fn sys_chdir(path: *const c_char) -> LinuxResult<usize> {
+    let path = arceos_posix_api::char_ptr_to_str(path)
+        .inspect_err(|err| warn!("Failed to convert path: {err:?}"))?;
 
     axfs::api::set_current_dir(path)
-        .map(|_| 0)
-        .unwrap_or_else(|err| {
-            warn!("Failed to change directory: {err:?}");
-            -1
-        })
+        .inspect_err(|err| warn!("Failed to change directory: {err:?}"))?;
+    Ok(0)
 }
 
-pub(crate) fn sys_mkdirat(dirfd: i32, path: *const c_char, mode: u32) -> c_int {
-    let path = match arceos_posix_api::char_ptr_to_str(path) {
-        Ok(path) => path,
-        Err(err) => {
-            warn!("Failed to convert path: {err:?}");
-            return -1;
-        }
-    };
-
-    if !path.starts_with("/") && dirfd != AT_FDCWD as i32 {
-        warn!("unsupported.");
-        return -1;
-    }
+pub(crate) fn sys_mkdirat(dirfd: i32, path: *const c_char, mode: u32) -> LinuxResult<usize> {
+    let path = user_path_at(dirfd, path)?;
 
     if mode != 0 {
         info!("directory mode not supported.");
     }
 
-    axfs::api::create_dir(path)
-        .map(|_| 0)
-        .unwrap_or_else(|err| {
-            warn!("Failed to create directory {path}: {err:?}");
-            -1
-        })
+    axfs::api::create_dir(&path)
+        .inspect_err(|err| warn!("Failed to create directory {path}: {err:?}"))?;
+    Ok(0)
 }
 
 #[repr(C)]
@@ -157,30 +139,26 @@ impl<'a> DirBuffer<'a> {
     }
 }
 
-pub(crate) fn sys_getdents64(fd: i32, buf: *mut c_void, len: usize) -> isize {
+pub(crate) fn sys_getdents64(fd: i32, buf: *mut c_void, len: usize) -> LinuxResult<usize> {
     if len < DirEnt::FIXED_SIZE {
         warn!("Buffer size too small: {len}");
-        return -1;
+        return Err(LinuxError::EINVAL);
     }
 
     let current_task = current();
-    if let Err(e) = current_task
+    current_task
         .task_ext()
         .aspace
         .lock()
         .alloc_for_lazy((buf as usize).into(), len)
-    {
-        warn!("Memory allocation failed: {:?}", e);
-        return -1;
-    }
+        .map_err(|e| {
+            warn!("Memory allocation failed: {:?}", e);
+            LinuxError::EFAULT
+        })?;
 
-    let path = match arceos_posix_api::Directory::from_fd(fd).map(|dir| dir.path().to_string()) {
-        Ok(path) => path,
-        Err(err) => {
-            warn!("Invalid directory descriptor: {:?}", err);
-            return -1;
-        }
-    };
+    let path = arceos_posix_api::Directory::from_fd(fd)
+        .map(|dir| dir.path().to_string())
+        .inspect_err(|err| warn!("Invalid directory descriptor: {:?}", err))?;
 
     let mut buffer =
         unsafe { DirBuffer::new(core::slice::from_raw_parts_mut(buf as *mut u8, len)) };
@@ -232,23 +210,23 @@ pub(crate) fn sys_getdents64(fd: i32, buf: *mut c_void, len: usize) -> isize {
                 let terminal = DirEnt::new(1, current_offset, 0, FileType::Reg);
                 let _ = buffer.write_entry(terminal, &[]);
             }
-            total_size as isize
+            total_size
         })
-        .unwrap_or(LinuxError::ENOENT as isize)
+        .map_err(|_| LinuxError::ENOENT)
 }
 
 /// create a link from new_path to old_path
 /// old_path: old file path
 /// new_path: new file path
 /// flags: link flags
-/// return value: return 0 when success, else return -1.
+/// return value: return 0 when success, else return the error.
 pub(crate) fn sys_linkat(
     old_dirfd: i32,
     old_path: *const u8,
     new_dirfd: i32,
     new_path: *const u8,
     flags: i32,
-) -> i32 {
+) -> LinuxResult<usize> {
     if flags != 0 {
         warn!("Unsupported flags: {flags}");
     }
@@ -269,44 +247,74 @@ pub(crate) fn sys_linkat(
                 .map_err(Into::into)
         })
         .map(|_| 0)
-        .unwrap_or(-1)
+        .map_err(LinuxError::from)
 }
 
 /// remove link of specific file (can be used to delete file)
 /// dir_fd: the directory of link to be removed
 /// path: the name of link to be removed
 /// flags: can be 0 or AT_REMOVEDIR
-/// return 0 when success, else return -1
-pub fn sys_unlinkat(dir_fd: isize, path: *const u8, flags: usize) -> isize {
-    const AT_REMOVEDIR: usize = 0x200;
-
-    arceos_posix_api::handle_file_path(dir_fd, Some(path), false)
-        .inspect_err(|e| warn!("unlinkat error: {:?}", e))
-        .and_then(|path| {
-            if flags == AT_REMOVEDIR {
-                axfs::api::remove_dir(path.as_str())
-                    .inspect_err(|e| warn!("unlinkat error: {:?}", e))
-                    .map(|_| 0)
-            } else {
-                axfs::api::metadata(path.as_str()).and_then(|metadata| {
-                    if metadata.is_dir() {
-                        Err(AxError::IsADirectory)
-                    } else {
-                        debug!("unlink file: {:?}", path);
-                        arceos_posix_api::HARDLINK_MANAGER
-                            .remove_link(&path)
-                            .ok_or_else(|| {
-                                debug!("unlink file error");
-                                AxError::NotFound
-                            })
-                            .map(|_| 0)
-                    }
-                })
-            }
-        })
-        .unwrap_or(-1)
+/// return 0 when success, else return the error
+pub fn sys_unlinkat(dir_fd: i32, path: *const c_char, flags: u32) -> LinuxResult<usize> {
+    if flags & !AT_REMOVEDIR != 0 {
+        warn!("Unsupported flags: {flags:#x}");
+        return Err(LinuxError::EINVAL);
+    }
+    let path = user_path_at(dir_fd, path)?;
+
+    if flags & AT_REMOVEDIR != 0 {
+        axfs::api::remove_dir(&path).inspect_err(|e| warn!("unlinkat error: {:?}", e))?;
+        return Ok(0);
+    }
+    if axfs::api::metadata(&path)?.is_dir() {
+        return Err(LinuxError::EISDIR);
+    }
+    debug!("unlink file: {:?}", path);
+    HARDLINK_MANAGER
+        .remove_link(&FilePath::new(&path)?)
+        .ok_or_else(|| {
+            debug!("unlink file error");
+            LinuxError::ENOENT
+        })?;
+    Ok(0)
+}
+
+/// Rename `old_path` relative to `old_dirfd` to `new_path` relative to
+/// `new_dirfd`, replacing the file at `new_path` if it exists.
+///
+/// None of the `RENAME_*` flags is supported, so `flags` must be 0.
+pub(crate) fn sys_renameat2(
+    old_dirfd: i32,
+    old_path: *const c_char,
+    new_dirfd: i32,
+    new_path: *const c_char,
+    flags: u32,
+) -> LinuxResult<usize> {
+    if flags != 0 {
+        warn!("Unsupported flags: {flags:#x}");
+        return Err(LinuxError::EINVAL);
+    }
+    let old_path = user_path_at(old_dirfd, old_path)?;
+    let new_path = user_path_at(new_dirfd, new_path)?;
+    axfs::api::rename(&old_path, &new_path)
+        .inspect_err(|err| warn!("Failed to rename {old_path} to {new_path}: {err:?}"))?;
+    Ok(0)
+}
+
+/// Check whether the file at `path` relative to `dirfd` can be accessed in
+/// `mode`, a combination of `R_OK`, `W_OK` and `X_OK`, or `F_OK` (0) to check
+/// whether it exists.
+///
+/// The tasks run as root, so any existing file can be accessed.
+pub(crate) fn sys_faccessat(dirfd: i32, path: *const c_char, mode: u32) -> LinuxResult<usize> {
+    if mode & !0o7 != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let path = user_path_at(dirfd, path)?;
+    axfs::api::metadata(&path)?;
+    Ok(0)
 }
 
-pub(crate) fn sys_getcwd(buf: *mut c_char, size: usize) -> *mut c_char {
-    arceos_posix_api::sys_getcwd(buf, size)
+pub(crate) fn sys_getcwd(buf: *mut c_char, size: usize) -> LinuxResult<usize> {
+    posix_result(arceos_posix_api::sys_getcwd(buf, size) as _)
 }
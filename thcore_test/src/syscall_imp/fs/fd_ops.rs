@@ -1,15 +1,18 @@
 use core::ffi::c_int;
 
 use arceos_posix_api as api;
+use axerrno::LinuxResult;
 
-pub(crate) fn sys_dup(old_fd: c_int) -> c_int {
-    api::sys_dup(old_fd)
+use crate::syscall_imp::posix_result;
+
+pub(crate) fn sys_dup(old_fd: c_int) -> LinuxResult<usize> {
+    posix_result(api::sys_dup(old_fd) as _)
 }
 
-pub(crate) fn sys_dup3(old_fd: c_int, new_fd: c_int) -> c_int {
-    api::sys_dup2(old_fd, new_fd)
+pub(crate) fn sys_dup3(old_fd: c_int, new_fd: c_int) -> LinuxResult<usize> {
+    posix_result(api::sys_dup2(old_fd, new_fd) as _)
 }
 
-pub(crate) fn sys_close(fd: c_int) -> c_int {
-    api::sys_close(fd)
+pub(crate) fn sys_close(fd: c_int) -> LinuxResult<usize> {
+    posix_result(api::sys_close(fd) as _)
 }
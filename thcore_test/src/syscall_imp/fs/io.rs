@@ -1,19 +1,160 @@
 use core::ffi::{c_char, c_void};
 
-use arceos_posix_api::{self as api, ctypes::mode_t};
+use alloc::vec::Vec;
+use arceos_posix_api::{
+    self as api,
+    ctypes::{iovec, mode_t},
+};
+use axerrno::{LinuxError, LinuxResult};
+use axhal::paging::MappingFlags;
+use axtask::{TaskExtRef, current};
 
-pub(crate) fn sys_read(fd: i32, buf: *mut c_void, count: usize) -> isize {
-    api::sys_read(fd, buf, count)
+use super::path::user_path_at;
+use crate::syscall_imp::posix_result;
+
+/// The maximum number of iovecs accepted by `readv`/`writev` (`UIO_MAXIOV` in Linux).
+const IOV_MAX: usize = 1024;
+
+/// Check that a user buffer allows the `access` (`READ` to be written to a
+/// file, `WRITE` to be read into), and populate its lazy mappings before it's
+/// passed to the file system.
+///
+/// A page fault on the buffer may read the page from the executable file, but
+/// the file system is not reentrant while it's serving the I/O.
+fn populate_user_buffer(buf: *const c_void, count: usize, access: MappingFlags) -> LinuxResult<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    if buf.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let curr = current();
+    let mut aspace = curr.task_ext().aspace.lock();
+    if !aspace.can_access_range((buf as usize).into(), count, access) {
+        return Err(LinuxError::EFAULT);
+    }
+    aspace
+        .alloc_for_lazy((buf as usize).into(), count)
+        .map_err(|_| LinuxError::EFAULT)
+}
+
+pub(crate) fn sys_read(fd: i32, buf: *mut c_void, count: usize) -> LinuxResult<usize> {
+    populate_user_buffer(buf, count, MappingFlags::WRITE)?;
+    posix_result(api::sys_read(fd, buf, count))
+}
+
+pub(crate) fn sys_write(fd: i32, buf: *const c_void, count: usize) -> LinuxResult<usize> {
+    populate_user_buffer(buf, count, MappingFlags::READ)?;
+    posix_result(api::sys_write(fd, buf, count))
 }
 
-pub(crate) fn sys_write(fd: i32, buf: *const c_void, count: usize) -> isize {
-    api::sys_write(fd, buf, count)
+/// Copy the iovec array from user memory and validate every entry in it.
+///
+/// Zero-length entries are kept (they are skipped by the callers), the other
+/// buffers must be mapped in the user address space with the `access` (see
+/// [`populate_user_buffer`]) and their total length must not exceed
+/// `isize::MAX`.
+fn load_iovecs(iov: *const iovec, iocnt: i32, access: MappingFlags) -> LinuxResult<Vec<iovec>> {
+    if iocnt < 0 || iocnt as usize > IOV_MAX {
+        return Err(LinuxError::EINVAL);
+    }
+    let iocnt = iocnt as usize;
+    if iocnt == 0 {
+        return Ok(Vec::new());
+    }
+    if iov.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+
+    let curr = current();
+    let mut aspace = curr.task_ext().aspace.lock();
+    let iov_size = iocnt * core::mem::size_of::<iovec>();
+    if !aspace.can_access_range((iov as usize).into(), iov_size, MappingFlags::READ) {
+        return Err(LinuxError::EFAULT);
+    }
+    aspace
+        .alloc_for_lazy((iov as usize).into(), iov_size)
+        .map_err(|_| LinuxError::EFAULT)?;
+    let iovs = unsafe { core::slice::from_raw_parts(iov, iocnt) }.to_vec();
+
+    let mut total: usize = 0;
+    for iov in iovs.iter().filter(|iov| iov.iov_len != 0) {
+        let len = iov.iov_len;
+        total = total
+            .checked_add(len)
+            .filter(|&total| total <= isize::MAX as usize)
+            .ok_or(LinuxError::EINVAL)?;
+        if iov.iov_base.is_null()
+            || !aspace.can_access_range((iov.iov_base as usize).into(), len, access)
+        {
+            return Err(LinuxError::EFAULT);
+        }
+        aspace
+            .alloc_for_lazy((iov.iov_base as usize).into(), len)
+            .map_err(|_| LinuxError::EFAULT)?;
+    }
+    Ok(iovs)
+}
+
+/// Read data from `fd` into multiple buffers.
+///
+/// The buffers are filled in order; a short read stops the transfer. An error
+/// is only reported if nothing has been transferred yet, otherwise the number
+/// of bytes read so far is returned.
+pub(crate) fn sys_readv(fd: i32, iov: *const iovec, iocnt: i32) -> LinuxResult<usize> {
+    let file = api::get_file_like(fd)?;
+    let iovs = load_iovecs(iov, iocnt, MappingFlags::WRITE)?;
+
+    let mut total = 0;
+    for iov in iovs.iter().filter(|iov| iov.iov_len != 0) {
+        let buf = unsafe { core::slice::from_raw_parts_mut(iov.iov_base as *mut u8, iov.iov_len) };
+        match file.read(buf) {
+            Ok(n) => {
+                total += n;
+                if n < buf.len() {
+                    break;
+                }
+            }
+            Err(e) if total == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(total)
 }
 
-pub(crate) fn sys_writev(fd: i32, iov: *const api::ctypes::iovec, iocnt: i32) -> isize {
-    unsafe { api::sys_writev(fd, iov, iocnt) }
+/// Write data to `fd` from multiple buffers.
+///
+/// The buffers are written in order; a short write stops the transfer. An
+/// error is only reported if nothing has been transferred yet, otherwise the
+/// number of bytes written so far is returned.
+pub(crate) fn sys_writev(fd: i32, iov: *const iovec, iocnt: i32) -> LinuxResult<usize> {
+    let file = api::get_file_like(fd)?;
+    let iovs = load_iovecs(iov, iocnt, MappingFlags::READ)?;
+
+    let mut total = 0;
+    for iov in iovs.iter().filter(|iov| iov.iov_len != 0) {
+        let buf = unsafe { core::slice::from_raw_parts(iov.iov_base as *const u8, iov.iov_len) };
+        match file.write(buf) {
+            Ok(n) => {
+                total += n;
+                if n < buf.len() {
+                    break;
+                }
+            }
+            Err(e) if total == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(total)
 }
 
-pub(crate) fn sys_openat(dirfd: i32, path: *const c_char, flags: i32, modes: mode_t) -> isize {
-    api::sys_openat(dirfd, path, flags, modes) as isize
+pub(crate) fn sys_openat(
+    dirfd: i32,
+    path: *const c_char,
+    flags: i32,
+    modes: mode_t,
+) -> LinuxResult<usize> {
+    let mut path = user_path_at(dirfd, path)?;
+    path.push('\0');
+    posix_result(api::sys_open(path.as_ptr() as _, flags, modes) as _)
 }
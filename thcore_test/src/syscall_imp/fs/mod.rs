@@ -1,11 +1,14 @@
 mod ctl;
 mod fd_ops;
 mod io;
+mod path;
 mod pipe;
+mod poll;
 mod stat;
 
 pub(crate) use self::ctl::*;
 pub(crate) use self::fd_ops::*;
 pub(crate) use self::io::*;
 pub(crate) use self::pipe::*;
+pub(crate) use self::poll::*;
 pub(crate) use self::stat::*;
@@ -0,0 +1,77 @@
+use core::ffi::c_char;
+
+use alloc::{format, string::String, vec::Vec};
+use arceos_posix_api::{AT_FDCWD, Directory};
+use axerrno::{LinuxError, LinuxResult};
+use axhal::paging::MappingFlags;
+use axtask::{TaskExtRef, current};
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr};
+
+/// The maximum length of a path, including the terminating NUL.
+const PATH_MAX: usize = 4096;
+
+/// Copy a NUL-terminated path from the user space.
+///
+/// The string is copied page by page after populating the lazy pages, so a
+/// bad pointer fails with `EFAULT` instead of faulting in the kernel. A path
+/// without NUL in the first [`PATH_MAX`] bytes fails with `ENAMETOOLONG`.
+pub(crate) fn read_user_path(ptr: *const c_char) -> LinuxResult<String> {
+    if ptr.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let curr = current();
+    let mut aspace = curr.task_ext().aspace.lock();
+    let mut bytes = Vec::new();
+    let mut addr = VirtAddr::from(ptr as usize);
+    while bytes.len() < PATH_MAX {
+        let len = (PAGE_SIZE_4K - addr.align_offset_4k()).min(PATH_MAX - bytes.len());
+        aspace
+            .alloc_for_lazy(addr, len)
+            .map_err(|_| LinuxError::EFAULT)?;
+        match aspace.page_table().query(addr) {
+            Ok((_, flags, _)) if flags.contains(MappingFlags::READ) => {}
+            _ => return Err(LinuxError::EFAULT),
+        }
+        let start = bytes.len();
+        bytes.resize(start + len, 0);
+        aspace
+            .read(addr, &mut bytes[start..])
+            .map_err(|_| LinuxError::EFAULT)?;
+        if let Some(end) = bytes[start..].iter().position(|&b| b == 0) {
+            bytes.truncate(start + end);
+            return String::from_utf8(bytes).map_err(|_| LinuxError::EINVAL);
+        }
+        addr += len;
+    }
+    Err(LinuxError::ENAMETOOLONG)
+}
+
+/// Resolve `path` relative to the directory `dirfd` as the `*at` syscalls do:
+///
+/// - an absolute path is used as is, ignoring `dirfd`;
+/// - with `AT_FDCWD`, a relative path is left to `axfs`, which resolves it
+///   against the current directory of the task;
+/// - otherwise, a relative path is joined to the path of the directory opened
+///   as `dirfd`, failing with `EBADF` if `dirfd` is not open, or `ENOTDIR` if
+///   it's not a directory.
+///
+/// An empty path fails with `ENOENT`.
+pub(crate) fn resolve_at(dirfd: i32, path: String) -> LinuxResult<String> {
+    if path.is_empty() {
+        return Err(LinuxError::ENOENT);
+    }
+    if path.starts_with('/') || dirfd == AT_FDCWD as i32 {
+        return Ok(path);
+    }
+    let dir = arceos_posix_api::get_file_like(dirfd)?
+        .into_any()
+        .downcast::<Directory>()
+        .map_err(|_| LinuxError::ENOTDIR)?;
+    Ok(format!("{}/{}", dir.path().trim_end_matches('/'), path))
+}
+
+/// Copy the path at `ptr` from the user space, and resolve it relative to
+/// `dirfd` with [`resolve_at`].
+pub(crate) fn user_path_at(dirfd: i32, ptr: *const c_char) -> LinuxResult<String> {
+    resolve_at(dirfd, read_user_path(ptr)?)
+}
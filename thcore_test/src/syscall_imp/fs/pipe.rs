@@ -1,8 +1,11 @@
 use core::ffi::c_int;
 
 use arceos_posix_api as api;
+use axerrno::LinuxResult;
 
-pub(crate) fn sys_pipe2(fds: *mut i32) -> c_int {
+use crate::syscall_imp::posix_result;
+
+pub(crate) fn sys_pipe2(fds: *mut i32) -> LinuxResult<usize> {
     let fds_slice: &mut [c_int] = unsafe { core::slice::from_raw_parts_mut(fds, 2) };
-    api::sys_pipe(fds_slice)
+    posix_result(api::sys_pipe(fds_slice) as _)
 }
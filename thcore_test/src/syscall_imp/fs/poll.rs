@@ -0,0 +1,267 @@
+use core::{ffi::c_void, time::Duration};
+
+use alloc::{sync::Arc, vec::Vec};
+use arceos_posix_api::{self as api, FD_LIMIT, FileLike, Poller};
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::monotonic_time;
+
+use crate::ctypes::{PollEvents, PollFd};
+
+/// The interval to poll the files again while waiting, if some of them don't
+/// notify the [`Poller`] when they become ready, see
+/// [`FileLike::poll_waiters`].
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The size of `sigset_t` in the kernel.
+const SIGSET_SIZE: usize = 8;
+
+/// The number of fds in `fd_set`.
+const FD_SETSIZE: usize = 1024;
+
+const BITS_PER_USIZE: usize = usize::BITS as usize;
+
+/// Read the optional timeout of `ppoll` and `pselect6`.
+fn load_timeout(timeout: *const api::ctypes::timespec) -> LinuxResult<Option<Duration>> {
+    if timeout.is_null() {
+        return Ok(None);
+    }
+    let ts = unsafe { *timeout };
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Some(ts.into()))
+}
+
+/// Fill in `revents` of every entry in `fds` with the current state of the
+/// file, and return the number of entries with nonzero `revents`.
+///
+/// Negative fds are ignored, and `POLLNVAL` is returned for the closed ones.
+/// `POLLHUP` and `POLLERR` are returned whether they are requested or not.
+fn poll_once(fds: &mut [PollFd]) -> usize {
+    let mut ready = 0;
+    for pfd in fds.iter_mut() {
+        pfd.revents = 0;
+        if pfd.fd < 0 {
+            continue;
+        }
+        let events = PollEvents::from_bits_truncate(pfd.events);
+        let revents = match api::get_file_like(pfd.fd) {
+            Err(_) => PollEvents::POLLNVAL,
+            Ok(file) => match file.poll() {
+                Ok(state) => {
+                    let mut revents = PollEvents::empty();
+                    if state.readable {
+                        revents |= events & (PollEvents::POLLIN | PollEvents::POLLRDNORM);
+                    }
+                    if state.writable {
+                        revents |= events & (PollEvents::POLLOUT | PollEvents::POLLWRNORM);
+                    }
+                    let conditions = file.poll_conditions();
+                    if conditions.hangup {
+                        revents |= PollEvents::POLLHUP;
+                    }
+                    if conditions.error {
+                        revents |= PollEvents::POLLERR;
+                    }
+                    revents
+                }
+                Err(_) => PollEvents::POLLERR,
+            },
+        };
+        pfd.revents = revents.bits();
+        if !revents.is_empty() {
+            ready += 1;
+        }
+    }
+    ready
+}
+
+/// Wait until any entry in `fds` is ready or `timeout` has elapsed, and
+/// return the number of ready entries.
+///
+/// This is the core of `ppoll` and `pselect6`. A zero timeout returns
+/// immediately, and no timeout waits forever.
+///
+/// The task sleeps until one of the files notifies a change, or the timeout
+/// expires. If some of the files can't notify, it wakes up every
+/// [`POLL_INTERVAL`] to poll them again.
+fn poll_fds(fds: &mut [PollFd], timeout: Option<Duration>) -> LinuxResult<usize> {
+    let deadline = timeout.map(|timeout| monotonic_time() + timeout);
+    let poller = Poller::new();
+    // The files are held until the end, so that they can be unregistered from.
+    let files: Vec<Arc<dyn FileLike>> = fds
+        .iter()
+        .filter(|pfd| pfd.fd >= 0)
+        .filter_map(|pfd| api::get_file_like(pfd.fd).ok())
+        .collect();
+    let mut periodic = false;
+    for file in &files {
+        match file.poll_waiters() {
+            Some(waiters) => waiters.register(&poller),
+            None => periodic = true,
+        }
+    }
+
+    // A change after registering wakes up the poller, even if it happens
+    // before waiting, so it's never missed.
+    let res = loop {
+        let ready = poll_once(fds);
+        if ready > 0 {
+            break Ok(ready);
+        }
+        // A killed process gives up here, and exits on the way back to the
+        // user space.
+        if axtask::current().is_interrupted() {
+            break Err(LinuxError::EINTR);
+        }
+        let now = monotonic_time();
+        let wait = match deadline {
+            Some(deadline) if now >= deadline => break Ok(0),
+            Some(deadline) if periodic => Some((deadline - now).min(POLL_INTERVAL)),
+            Some(deadline) => Some(deadline - now),
+            None if periodic => Some(POLL_INTERVAL),
+            None => None,
+        };
+        match wait {
+            Some(wait) => poller.wait_timeout(wait),
+            None => poller.wait(),
+        }
+    };
+
+    for file in &files {
+        if let Some(waiters) = file.poll_waiters() {
+            waiters.unregister(&poller);
+        }
+    }
+    res
+}
+
+/// Wait for some event on a set of file descriptors.
+///
+/// There are no signals yet, so `sigmask` is only checked for its size, and
+/// the wait is only interrupted when the process is killed.
+pub(crate) fn sys_ppoll(
+    fds: *mut PollFd,
+    nfds: usize,
+    timeout: *const api::ctypes::timespec,
+    sigmask: *const c_void,
+    sigsetsize: usize,
+) -> LinuxResult<usize> {
+    if nfds > FD_LIMIT.get() {
+        return Err(LinuxError::EINVAL);
+    }
+    if !sigmask.is_null() && sigsetsize != SIGSET_SIZE {
+        return Err(LinuxError::EINVAL);
+    }
+    if fds.is_null() && nfds > 0 {
+        return Err(LinuxError::EFAULT);
+    }
+    let timeout = load_timeout(timeout)?;
+    let mut poll_fds_buf = if nfds == 0 {
+        Vec::new()
+    } else {
+        unsafe { core::slice::from_raw_parts(fds, nfds) }.to_vec()
+    };
+
+    let ready = poll_fds(&mut poll_fds_buf, timeout)?;
+    for (i, pfd) in poll_fds_buf.iter().enumerate() {
+        unsafe { (*fds.add(i)).revents = pfd.revents };
+    }
+    Ok(ready)
+}
+
+/// Get whether `fd` is in the `fd_set` at `set`, which may be NULL.
+fn fd_isset(set: *const usize, fd: usize) -> bool {
+    !set.is_null() && unsafe { *set.add(fd / BITS_PER_USIZE) } & (1 << (fd % BITS_PER_USIZE)) != 0
+}
+
+/// Clear the first `nfds` fds of the `fd_set` at `set`, which may be NULL.
+fn fd_zero(set: *mut usize, nfds: usize) {
+    if !set.is_null() {
+        unsafe { core::slice::from_raw_parts_mut(set, nfds.div_ceil(BITS_PER_USIZE)) }.fill(0);
+    }
+}
+
+/// Add `fd` to the `fd_set` at `set`, which may be NULL.
+fn fd_set(set: *mut usize, fd: usize) {
+    if !set.is_null() {
+        unsafe { *set.add(fd / BITS_PER_USIZE) |= 1 << (fd % BITS_PER_USIZE) };
+    }
+}
+
+/// Synchronous I/O multiplexing, implemented on top of `ppoll`.
+///
+/// The fds in the sets are converted to [`PollFd`]s, and the sets are
+/// replaced with the ready ones. Like `sys_ppoll`, the signal mask is
+/// ignored.
+pub(crate) fn sys_pselect6(
+    nfds: i32,
+    readfds: *mut usize,
+    writefds: *mut usize,
+    exceptfds: *mut usize,
+    timeout: *const api::ctypes::timespec,
+    _sigmask: *const c_void,
+) -> LinuxResult<usize> {
+    if nfds < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let nfds = (nfds as usize).min(FD_SETSIZE);
+    let timeout = load_timeout(timeout)?;
+
+    let mut poll_fds_buf = Vec::new();
+    for fd in 0..nfds {
+        let mut events = PollEvents::empty();
+        if fd_isset(readfds, fd) {
+            events |= PollEvents::POLLIN;
+        }
+        if fd_isset(writefds, fd) {
+            events |= PollEvents::POLLOUT;
+        }
+        if fd_isset(exceptfds, fd) {
+            events |= PollEvents::POLLPRI;
+        }
+        if !events.is_empty() {
+            poll_fds_buf.push(PollFd {
+                fd: fd as i32,
+                events: events.bits(),
+                revents: 0,
+            });
+        }
+    }
+
+    poll_fds(&mut poll_fds_buf, timeout)?;
+    // Unlike `poll`, `select` fails on closed fds.
+    if poll_fds_buf
+        .iter()
+        .any(|pfd| PollEvents::from_bits_truncate(pfd.revents).contains(PollEvents::POLLNVAL))
+    {
+        return Err(LinuxError::EBADF);
+    }
+
+    fd_zero(readfds, nfds);
+    fd_zero(writefds, nfds);
+    fd_zero(exceptfds, nfds);
+    let mut ready = 0;
+    for pfd in poll_fds_buf {
+        let events = PollEvents::from_bits_truncate(pfd.events);
+        let revents = PollEvents::from_bits_truncate(pfd.revents);
+        let fd = pfd.fd as usize;
+        let error = revents.contains(PollEvents::POLLERR);
+        // Reading a file that is hung up doesn't block either.
+        let readable = revents.intersects(PollEvents::POLLIN | PollEvents::POLLHUP);
+        if events.contains(PollEvents::POLLIN) && (readable || error) {
+            fd_set(readfds, fd);
+            ready += 1;
+        }
+        if events.contains(PollEvents::POLLOUT) && (revents.contains(PollEvents::POLLOUT) || error)
+        {
+            fd_set(writefds, fd);
+            ready += 1;
+        }
+        if revents.contains(PollEvents::POLLPRI) {
+            fd_set(exceptfds, fd);
+            ready += 1;
+        }
+    }
+    Ok(ready)
+}
@@ -1,159 +1,146 @@
-use core::ffi::c_void;
-
-use axerrno::LinuxError;
-
-use crate::syscall_body;
-
-#[derive(Debug, Clone, Copy, Default)]
-#[repr(C)]
-pub struct Kstat {
-    /// 设备
-    pub st_dev: u64,
-    /// inode 编号
-    pub st_ino: u64,
-    /// 文件类型
-    pub st_mode: u32,
-    /// 硬链接数
-    pub st_nlink: u32,
-    /// 用户id
-    pub st_uid: u32,
-    /// 用户组id
-    pub st_gid: u32,
-    /// 设备号
-    pub st_rdev: u64,
-    /// padding
-    pub _pad0: u64,
-    /// 文件大小
-    pub st_size: u64,
-    /// 块大小
-    pub st_blksize: u32,
-    /// padding
-    pub _pad1: u32,
-    /// 块个数
-    pub st_blocks: u64,
-    /// 最后一次访问时间(秒)
-    pub st_atime_sec: isize,
-    /// 最后一次访问时间(纳秒)
-    pub st_atime_nsec: isize,
-    /// 最后一次修改时间(秒)
-    pub st_mtime_sec: isize,
-    /// 最后一次修改时间(纳秒)
-    pub st_mtime_nsec: isize,
-    /// 最后一次改变状态时间(秒)
-    pub st_ctime_sec: isize,
-    /// 最后一次改变状态时间(纳秒)
-    pub st_ctime_nsec: isize,
+use core::ffi::{c_char, c_void};
+
+use arceos_posix_api::{self as api, AT_FDCWD, FilePath, HARDLINK_MANAGER, ctypes};
+use axerrno::{LinuxError, LinuxResult};
+
+use crate::ctypes::{Kstat, StatX, StatxMask, StatxTimestamp};
+
+const AT_SYMLINK_NOFOLLOW: u32 = 0x100;
+const AT_NO_AUTOMOUNT: u32 = 0x800;
+const AT_EMPTY_PATH: u32 = 0x1000;
+const AT_STATX_SYNC_TYPE: u32 = 0x6000;
+
+impl From<ctypes::stat> for Kstat {
+    fn from(stat: ctypes::stat) -> Self {
+        Self {
+            st_dev: stat.st_dev as _,
+            st_ino: stat.st_ino as _,
+            st_mode: stat.st_mode as _,
+            st_nlink: stat.st_nlink as _,
+            st_uid: stat.st_uid as _,
+            st_gid: stat.st_gid as _,
+            st_rdev: stat.st_rdev as _,
+            st_size: stat.st_size as _,
+            st_blksize: stat.st_blksize as _,
+            st_blocks: stat.st_blocks as _,
+            st_atime_sec: stat.st_atime.tv_sec as _,
+            st_atime_nsec: stat.st_atime.tv_nsec as _,
+            st_mtime_sec: stat.st_mtime.tv_sec as _,
+            st_mtime_nsec: stat.st_mtime.tv_nsec as _,
+            st_ctime_sec: stat.st_ctime.tv_sec as _,
+            st_ctime_nsec: stat.st_ctime.tv_nsec as _,
+            ..Default::default()
+        }
+    }
 }
 
-impl From<arceos_posix_api::ctypes::stat> for Kstat {
-    fn from(stat: arceos_posix_api::ctypes::stat) -> Self {
+impl From<ctypes::stat> for StatX {
+    fn from(stat: ctypes::stat) -> Self {
         Self {
-            st_dev: stat.st_dev,
-            st_ino: stat.st_ino,
-            st_mode: stat.st_mode,
-            st_nlink: stat.st_nlink,
-            st_uid: stat.st_uid,
-            st_gid: stat.st_gid,
-            st_rdev: stat.st_rdev,
-            _pad0: 0,
-            st_size: stat.st_size as u64,
-            st_blksize: stat.st_blksize as u32,
-            _pad1: 0,
-            st_blocks: stat.st_blocks as u64,
-            st_atime_sec: stat.st_atime.tv_sec as isize,
-            st_atime_nsec: stat.st_atime.tv_nsec as isize,
-            st_mtime_sec: stat.st_mtime.tv_sec as isize,
-            st_mtime_nsec: stat.st_mtime.tv_nsec as isize,
-            st_ctime_sec: stat.st_ctime.tv_sec as isize,
-            st_ctime_nsec: stat.st_ctime.tv_nsec as isize,
+            stx_mask: StatxMask::STATX_BASIC_STATS.bits(),
+            stx_blksize: stat.st_blksize as _,
+            stx_nlink: stat.st_nlink as _,
+            stx_uid: stat.st_uid as _,
+            stx_gid: stat.st_gid as _,
+            stx_mode: stat.st_mode as _,
+            stx_ino: stat.st_ino as _,
+            stx_size: stat.st_size as _,
+            stx_blocks: stat.st_blocks as _,
+            stx_atime: StatxTimestamp {
+                tv_sec: stat.st_atime.tv_sec as _,
+                tv_nsec: stat.st_atime.tv_nsec as _,
+                ..Default::default()
+            },
+            stx_ctime: StatxTimestamp {
+                tv_sec: stat.st_ctime.tv_sec as _,
+                tv_nsec: stat.st_ctime.tv_nsec as _,
+                ..Default::default()
+            },
+            stx_mtime: StatxTimestamp {
+                tv_sec: stat.st_mtime.tv_sec as _,
+                tv_nsec: stat.st_mtime.tv_nsec as _,
+                ..Default::default()
+            },
+            ..Default::default()
         }
     }
 }
 
-pub(crate) fn sys_fstat(fd: i32, kstatbuf: *mut c_void) -> i32 {
-    let kstatbuf = kstatbuf as *mut Kstat;
-    let mut statbuf = arceos_posix_api::ctypes::stat::default();
+/// Get the status of the file at `path` from the metadata of the fs layer.
+fn stat_path(path: &FilePath) -> LinuxResult<ctypes::stat> {
+    let metadata = axfs::api::metadata(path.as_str())?;
+    let ty = metadata.file_type() as u32;
+    let perm = metadata.permissions().bits() as u32;
+    Ok(ctypes::stat {
+        st_ino: 1,
+        st_nlink: HARDLINK_MANAGER.link_count(path) as _,
+        st_mode: (ty << 12) | perm,
+        st_size: metadata.size() as _,
+        st_blocks: metadata.blocks() as _,
+        st_blksize: 512,
+        ..Default::default()
+    })
+}
 
-    if unsafe {
-        arceos_posix_api::sys_fstat(fd, &mut statbuf as *mut arceos_posix_api::ctypes::stat)
-    } < 0
-    {
-        return -1;
+/// Get the status of the file identified by `dirfd`, `path` and `flags`, in
+/// the way shared by `fstatat` and `statx`.
+fn stat_at(dirfd: i32, path: *const c_char, flags: u32) -> LinuxResult<ctypes::stat> {
+    if flags & !(AT_SYMLINK_NOFOLLOW | AT_NO_AUTOMOUNT | AT_EMPTY_PATH | AT_STATX_SYNC_TYPE) != 0 {
+        return Err(LinuxError::EINVAL);
     }
-
-    unsafe {
-        let kstat = Kstat::from(statbuf);
-        kstatbuf.write(kstat);
+    // A NULL path is accepted as an empty one since Linux 6.11.
+    let empty = path.is_null() || api::char_ptr_to_str(path)?.is_empty();
+    if empty {
+        if flags & AT_EMPTY_PATH == 0 {
+            return Err(if path.is_null() {
+                LinuxError::EFAULT
+            } else {
+                LinuxError::ENOENT
+            });
+        }
+        // The current directory has no file descriptor to stat.
+        if dirfd as isize == AT_FDCWD {
+            return stat_path(&FilePath::new(axfs::api::current_dir()?)?);
+        }
+        return api::get_file_like(dirfd)?.stat();
     }
-    0
+
+    // There is no symbolic link in the fs layer, so `AT_SYMLINK_NOFOLLOW`
+    // doesn't change the lookup.
+    let path = api::handle_file_path(dirfd as isize, Some(path as *const u8), false)?;
+    stat_path(&path)
 }
 
-#[repr(C)]
-#[derive(Debug, Default)]
-pub struct FsStatxTimestamp {
-    pub tv_sec: i64,
-    pub tv_nsec: u32,
+pub(crate) fn sys_fstat(fd: i32, kstatbuf: *mut c_void) -> LinuxResult<usize> {
+    if kstatbuf.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let stat = api::get_file_like(fd)?.stat()?;
+    unsafe { (kstatbuf as *mut Kstat).write(Kstat::from(stat)) };
+    Ok(0)
 }
 
-/// statx - get file status (extended)
-/// Standard C library (libc, -lc)
-/// <https://man7.org/linux/man-pages/man2/statx.2.html>
-#[repr(C)]
-#[derive(Debug, Default)]
-pub struct StatX {
-    /// Bitmask of what information to get.
-    pub stx_mask: u32,
-    /// Block size for filesystem I/O.
-    pub stx_blksize: u32,
-    /// File attributes.
-    pub stx_attributes: u64,
-    /// Number of hard links.
-    pub stx_nlink: u32,
-    /// User ID of owner.
-    pub stx_uid: u32,
-    /// Group ID of owner.
-    pub stx_gid: u32,
-    /// File mode (permissions).
-    pub stx_mode: u16,
-    /// Inode number.
-    pub stx_ino: u64,
-    /// Total size, in bytes.
-    pub stx_size: u64,
-    /// Number of 512B blocks allocated.
-    pub stx_blocks: u64,
-    /// Mask to show what's supported in stx_attributes.
-    pub stx_attributes_mask: u64,
-    /// Last access timestamp.
-    pub stx_atime: FsStatxTimestamp,
-    /// Birth (creation) timestamp.
-    pub stx_btime: FsStatxTimestamp,
-    /// Last status change timestamp.
-    pub stx_ctime: FsStatxTimestamp,
-    /// Last modification timestamp.
-    pub stx_mtime: FsStatxTimestamp,
-    /// Major device ID (if special file).
-    pub stx_rdev_major: u32,
-    /// Minor device ID (if special file).
-    pub stx_rdev_minor: u32,
-    /// Major device ID of file system.
-    pub stx_dev_major: u32,
-    /// Minor device ID of file system.
-    pub stx_dev_minor: u32,
-    /// Mount ID.
-    pub stx_mnt_id: u64,
-    /// Memory alignment for direct I/O.
-    pub stx_dio_mem_align: u32,
-    /// Offset alignment for direct I/O.
-    pub stx_dio_offset_align: u32,
+pub(crate) fn sys_fstatat(
+    dirfd: i32,
+    path: *const c_char,
+    kstatbuf: *mut c_void,
+    flags: u32,
+) -> LinuxResult<usize> {
+    if kstatbuf.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let stat = stat_at(dirfd, path, flags)?;
+    unsafe { (kstatbuf as *mut Kstat).write(Kstat::from(stat)) };
+    Ok(0)
 }
 
 pub(crate) fn sys_statx(
     dirfd: i32,
-    pathname: *const u8,
+    pathname: *const c_char,
     flags: u32,
-    _mask: u32,
+    mask: u32,
     statxbuf: *mut c_void,
-) -> i32 {
+) -> LinuxResult<usize> {
     // `statx()` uses pathname, dirfd, and flags to identify the target
     // file in one of the following ways:
 
@@ -181,40 +168,13 @@ pub(crate) fn sys_statx(
     //        below), then the target file is the one referred to by the
     //        file descriptor dirfd.
 
-    syscall_body!(sys_statx, {
-        let path = arceos_posix_api::char_ptr_to_str(pathname as *const _)?;
-
-        const AT_EMPTY_PATH: u32 = 0x1000;
-        if path.is_empty() {
-            if flags & AT_EMPTY_PATH == 0 {
-                return Err(LinuxError::EINVAL);
-            }
-            // Alloc a new space for stat struct
-            let mut status = arceos_posix_api::ctypes::stat::default();
-            let res = unsafe { arceos_posix_api::sys_fstat(dirfd, &mut status as *mut _) };
-            if res < 0 {
-                return Err(LinuxError::try_from(-res).unwrap());
-            }
-            let statx = unsafe { &mut *(statxbuf as *mut StatX) };
-            statx.stx_blksize = status.st_blksize as u32;
-            statx.stx_attributes = status.st_mode as u64;
-            statx.stx_nlink = status.st_nlink;
-            statx.stx_uid = status.st_uid;
-            statx.stx_gid = status.st_gid;
-            statx.stx_mode = status.st_mode as u16;
-            statx.stx_ino = status.st_ino;
-            statx.stx_size = status.st_size as u64;
-            statx.stx_blocks = status.st_blocks as u64;
-            statx.stx_attributes_mask = 0x7FF;
-            statx.stx_atime.tv_sec = status.st_atime.tv_sec;
-            statx.stx_atime.tv_nsec = status.st_atime.tv_nsec as u32;
-            statx.stx_ctime.tv_sec = status.st_ctime.tv_sec;
-            statx.stx_ctime.tv_nsec = status.st_ctime.tv_nsec as u32;
-            statx.stx_mtime.tv_sec = status.st_mtime.tv_sec;
-            statx.stx_mtime.tv_nsec = status.st_mtime.tv_nsec as u32;
-            Ok(0)
-        } else {
-            Err(LinuxError::ENOSYS)
-        }
-    })
+    if statxbuf.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let stat = stat_at(dirfd, pathname, flags)?;
+    let mut statx = StatX::from(stat);
+    // Only report the requested fields among those we are able to fill.
+    statx.stx_mask &= mask;
+    unsafe { (statxbuf as *mut StatX).write(statx) };
+    Ok(0)
 }
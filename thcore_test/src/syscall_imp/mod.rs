@@ -1,10 +1,16 @@
 mod fs;
 mod mm;
+#[cfg(feature = "strace")]
+pub(crate) mod strace;
 mod task;
 mod utils;
 
-use crate::task::{time_stat_from_kernel_to_user, time_stat_from_user_to_kernel};
-use axerrno::LinuxError;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "syscall_stats")]
+use alloc::vec::Vec;
+use arceos_posix_api::AT_FDCWD;
+use axerrno::{LinuxError, LinuxResult};
 use axhal::{
     arch::TrapFrame,
     trap::{SYSCALL, register_trap_handler},
@@ -13,108 +19,255 @@ use syscalls::Sysno;
 
 use self::fs::*;
 use self::mm::*;
+#[cfg(feature = "strace")]
+use self::strace::*;
 use self::task::*;
 use self::utils::*;
 
-/// Macro to generate syscall body
+/// The type of entries in [`SYSCALL_TABLE`].
 ///
-/// It will receive a function which return Result<_, LinuxError> and convert it to
-/// the type which is specified by the caller.
-#[macro_export]
-macro_rules! syscall_body {
-    ($fn: ident, $($stmt: tt)*) => {{
-        #[allow(clippy::redundant_closure_call)]
-        let res = (|| -> axerrno::LinuxResult<_> { $($stmt)* })();
-        match res {
-            Ok(_) | Err(axerrno::LinuxError::EAGAIN) => debug!(concat!(stringify!($fn), " => {:?}"),  res),
-            Err(_) => info!(concat!(stringify!($fn), " => {:?}"), res),
-        }
-        match res {
-            Ok(v) => v as _,
-            Err(e) => {
-                -e.code() as _
-            }
-        }
-    }};
+/// The arguments are taken from the trap frame, and the result is converted to
+/// the return value in the user space by [`handle_syscall`].
+type SyscallHandler = fn(&mut TrapFrame) -> LinuxResult<usize>;
+
+/// The size of [`SYSCALL_TABLE`], larger than any syscall number in use.
+const SYSCALL_TABLE_SIZE: usize = 512;
+
+/// Define [`SYSCALL_TABLE`] with one `name => handler` line per syscall.
+macro_rules! syscall_table {
+    ($($(#[$attr:meta])* $sysno:ident => $handler:expr,)*) => {
+        /// The syscall handlers indexed by syscall numbers.
+        static SYSCALL_TABLE: [Option<SyscallHandler>; SYSCALL_TABLE_SIZE] = {
+            let mut table: [Option<SyscallHandler>; SYSCALL_TABLE_SIZE] =
+                [None; SYSCALL_TABLE_SIZE];
+            $(
+                $(#[$attr])*
+                {
+                    let handler: SyscallHandler = $handler;
+                    table[Sysno::$sysno as usize] = Some(handler);
+                }
+            )*
+            table
+        };
+    };
 }
 
-#[register_trap_handler(SYSCALL)]
-fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
-    info!("Syscall {:?}", Sysno::from(syscall_num as u32));
-    time_stat_from_user_to_kernel();
-    let ans = match Sysno::from(syscall_num as u32) {
-        Sysno::read => sys_read(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
-        Sysno::write => sys_write(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
-        Sysno::mmap => sys_mmap(
+syscall_table! {
+    read => |tf| sys_read(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    write => |tf| sys_write(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    mmap => |tf| {
+        sys_mmap(
             tf.arg0() as _,
             tf.arg1() as _,
             tf.arg2() as _,
             tf.arg3() as _,
             tf.arg4() as _,
             tf.arg5() as _,
-        ) as _,
-        Sysno::ioctl => sys_ioctl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _) as _,
-        Sysno::writev => sys_writev(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
-        Sysno::sched_yield => sys_sched_yield() as isize,
-        Sysno::nanosleep => sys_nanosleep(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::getpid => sys_getpid() as isize,
-        Sysno::getppid => sys_getppid() as isize,
-        Sysno::exit => sys_exit(tf.arg0() as _),
-        Sysno::gettimeofday => sys_get_time_of_day(tf.arg0() as _) as _,
-        Sysno::getcwd => sys_getcwd(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::dup => sys_dup(tf.arg0() as _) as _,
-        Sysno::dup3 => sys_dup3(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::clone => sys_clone(
+        )
+    },
+    ioctl => |tf| sys_ioctl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    readv => |tf| sys_readv(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    writev => |tf| sys_writev(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    sched_yield => |_| sys_sched_yield(),
+    nanosleep => |tf| sys_nanosleep(tf.arg0() as _, tf.arg1() as _),
+    sched_setaffinity => |tf| sys_sched_setaffinity(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    getcpu => |tf| sys_getcpu(tf.arg0() as _, tf.arg1() as _),
+    getpid => |_| sys_getpid(),
+    getppid => |_| sys_getppid(),
+    gettid => |_| sys_gettid(),
+    getpgid => |tf| sys_getpgid(tf.arg0() as _),
+    setpgid => |tf| sys_setpgid(tf.arg0() as _, tf.arg1() as _),
+    getsid => |tf| sys_getsid(tf.arg0() as _),
+    exit => |tf| sys_exit(tf.arg0() as _),
+    gettimeofday => |tf| sys_get_time_of_day(tf.arg0() as _),
+    getcwd => |tf| sys_getcwd(tf.arg0() as _, tf.arg1() as _),
+    dup => |tf| sys_dup(tf.arg0() as _),
+    dup3 => |tf| sys_dup3(tf.arg0() as _, tf.arg1() as _),
+    clone => |tf| {
+        sys_clone(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+            tf.arg4() as _,
+        )
+    },
+    wait4 => |tf| sys_wait4(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    pipe2 => |tf| sys_pipe2(tf.arg0() as _),
+    ppoll => |tf| {
+        sys_ppoll(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+            tf.arg4() as _,
+        )
+    },
+    pselect6 => |tf| {
+        sys_pselect6(
             tf.arg0() as _,
             tf.arg1() as _,
             tf.arg2() as _,
             tf.arg3() as _,
             tf.arg4() as _,
-        ) as _,
-        Sysno::wait4 => sys_wait4(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _) as _,
-        Sysno::pipe2 => sys_pipe2(tf.arg0() as _) as _,
-        Sysno::close => sys_close(tf.arg0() as _) as _,
-        Sysno::chdir => sys_chdir(tf.arg0() as _) as _,
-        Sysno::mkdirat => sys_mkdirat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _) as _,
-        Sysno::execve => sys_execve(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _) as _,
-        Sysno::openat => sys_openat(
+            tf.arg5() as _,
+        )
+    },
+    close => |tf| sys_close(tf.arg0() as _),
+    chdir => |tf| sys_chdir(tf.arg0() as _),
+    mkdirat => |tf| sys_mkdirat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    execve => |tf| sys_execve(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    openat => |tf| sys_openat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+    getdents64 => |tf| sys_getdents64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    linkat => |tf| {
+        sys_linkat(
             tf.arg0() as _,
             tf.arg1() as _,
             tf.arg2() as _,
             tf.arg3() as _,
-        ) as _,
-        Sysno::getdents64 => sys_getdents64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
-        Sysno::linkat => sys_linkat(
+            tf.arg4() as _,
+        )
+    },
+    unlinkat => |tf| sys_unlinkat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    renameat => |tf| {
+        sys_renameat2(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _, 0)
+    },
+    renameat2 => |tf| {
+        sys_renameat2(
             tf.arg0() as _,
             tf.arg1() as _,
             tf.arg2() as _,
             tf.arg3() as _,
             tf.arg4() as _,
-        ) as _,
-        Sysno::unlinkat => sys_unlinkat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
-        Sysno::uname => sys_uname(tf.arg0() as _) as _,
-        Sysno::fstat => sys_fstat(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::statx => sys_statx(
+        )
+    },
+    faccessat => |tf| sys_faccessat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    #[cfg(target_arch = "x86_64")]
+    open => |tf| sys_openat(AT_FDCWD as _, tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+    #[cfg(target_arch = "x86_64")]
+    mkdir => |tf| sys_mkdirat(AT_FDCWD as _, tf.arg0() as _, tf.arg1() as _),
+    #[cfg(target_arch = "x86_64")]
+    unlink => |tf| sys_unlinkat(AT_FDCWD as _, tf.arg0() as _, 0),
+    #[cfg(target_arch = "x86_64")]
+    rmdir => |tf| sys_unlinkat(AT_FDCWD as _, tf.arg0() as _, AT_REMOVEDIR),
+    #[cfg(target_arch = "x86_64")]
+    rename => |tf| sys_renameat2(AT_FDCWD as _, tf.arg0() as _, AT_FDCWD as _, tf.arg1() as _, 0),
+    #[cfg(target_arch = "x86_64")]
+    access => |tf| sys_faccessat(AT_FDCWD as _, tf.arg0() as _, tf.arg1() as _),
+    uname => |tf| sys_uname(tf.arg0() as _),
+    fstat => |tf| sys_fstat(tf.arg0() as _, tf.arg1() as _),
+    #[cfg(target_arch = "x86_64")]
+    newfstatat => |tf| sys_fstatat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+    #[cfg(not(target_arch = "x86_64"))]
+    fstatat => |tf| sys_fstatat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+    statx => |tf| {
+        sys_statx(
             tf.arg0() as _,
             tf.arg1() as _,
             tf.arg2() as _,
             tf.arg3() as _,
             tf.arg4() as _,
-        ) as _,
-        Sysno::munmap => sys_munmap(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::times => sys_times(tf.arg0() as _) as _,
-        Sysno::brk => sys_brk(tf.arg0() as _) as _,
-        #[cfg(target_arch = "x86_64")]
-        Sysno::arch_prctl => sys_arch_prctl(tf.arg0() as _, tf.arg1() as _),
-        Sysno::set_tid_address => sys_set_tid_address(tf.arg0() as _),
-        Sysno::clock_gettime => sys_clock_gettime(tf.arg0() as _, tf.arg1() as _) as _,
-        Sysno::exit_group => sys_exit_group(tf.arg0() as _),
-        _ => {
-            warn!("Unimplemented syscall: {}", syscall_num);
-            axtask::exit(LinuxError::ENOSYS as _)
+        )
+    },
+    munmap => |tf| sys_munmap(tf.arg0() as _, tf.arg1() as _),
+    times => |tf| sys_times(tf.arg0() as _),
+    getrusage => |tf| sys_getrusage(tf.arg0() as _, tf.arg1() as _),
+    brk => |tf| sys_brk(tf.arg0() as _),
+    #[cfg(target_arch = "x86_64")]
+    arch_prctl => |tf| sys_arch_prctl(tf.arg0() as _, tf.arg1() as _),
+    set_tid_address => |tf| sys_set_tid_address(tf.arg0() as _),
+    prlimit64 => |tf| sys_prlimit64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+    getrlimit => |tf| sys_getrlimit(tf.arg0() as _, tf.arg1() as _),
+    setrlimit => |tf| sys_setrlimit(tf.arg0() as _, tf.arg1() as _),
+    clock_gettime => |tf| sys_clock_gettime(tf.arg0() as _, tf.arg1() as _),
+    #[cfg(feature = "strace")]
+    prctl => |tf| sys_prctl(tf.arg0() as _, tf.arg1() as _),
+    exit_group => |tf| sys_exit_group(tf.arg0() as _),
+}
+
+/// The number of calls to each syscall, for coverage reporting.
+#[cfg(feature = "syscall_stats")]
+static SYSCALL_COUNTS: [AtomicUsize; SYSCALL_TABLE_SIZE] =
+    [const { AtomicUsize::new(0) }; SYSCALL_TABLE_SIZE];
+
+/// Return the syscalls that have been invoked, with the number of calls.
+#[cfg(feature = "syscall_stats")]
+pub fn syscall_counts() -> Vec<(Sysno, usize)> {
+    SYSCALL_COUNTS
+        .iter()
+        .enumerate()
+        .filter_map(|(num, count)| match count.load(Ordering::Relaxed) {
+            0 => None,
+            count => Some((Sysno::new(num)?, count)),
+        })
+        .collect()
+}
+
+/// The number of calls to each unimplemented syscall, to limit the logs.
+static UNIMPLEMENTED_COUNTS: [AtomicUsize; SYSCALL_TABLE_SIZE] =
+    [const { AtomicUsize::new(0) }; SYSCALL_TABLE_SIZE];
+
+/// Handle an unimplemented syscall with `ENOSYS`.
+///
+/// It's logged on the 1st, 2nd, 4th, 8th... call of each syscall number, so
+/// that a program polling an unimplemented syscall doesn't flood the log.
+fn unimplemented_syscall(syscall_num: usize) -> LinuxResult<usize> {
+    let count = match UNIMPLEMENTED_COUNTS.get(syscall_num) {
+        Some(count) => count.fetch_add(1, Ordering::Relaxed) + 1,
+        None => 1,
+    };
+    if count.is_power_of_two() {
+        warn!(
+            "Unimplemented syscall: {} ({}), called {} times",
+            syscall_num,
+            Sysno::new(syscall_num).map_or("unknown", |sysno| sysno.name()),
+            count
+        );
+    }
+    Err(LinuxError::ENOSYS)
+}
+
+/// Convert the return value of a syscall implemented by `arceos_posix_api`,
+/// which is a negative error number on failure, into a [`LinuxResult`].
+fn posix_result(ret: isize) -> LinuxResult<usize> {
+    if ret >= 0 {
+        Ok(ret as usize)
+    } else {
+        Err(LinuxError::try_from(-ret as i32).unwrap_or(LinuxError::EINVAL))
+    }
+}
+
+#[register_trap_handler(SYSCALL)]
+fn handle_syscall(tf: &mut TrapFrame, syscall_num: usize) -> isize {
+    let Some(sysno) = Sysno::new(syscall_num) else {
+        // Not a syscall number of this architecture at all, so there is no
+        // handler for it either.
+        let _ = unimplemented_syscall(syscall_num);
+        return -LinuxError::ENOSYS.code() as isize;
+    };
+    info!("Syscall {:?}", sysno);
+    #[cfg(feature = "strace")]
+    let trace = SyscallTrace::enter(sysno, tf);
+    let res = match SYSCALL_TABLE.get(syscall_num).copied().flatten() {
+        Some(handler) => {
+            #[cfg(feature = "syscall_stats")]
+            SYSCALL_COUNTS[syscall_num].fetch_add(1, Ordering::Relaxed);
+            handler(tf)
         }
+        None => unimplemented_syscall(syscall_num),
+    };
+    match res {
+        Ok(_) | Err(LinuxError::EAGAIN) => debug!("{:?} => {:?}", sysno, res),
+        Err(_) => info!("{:?} => {:?}", sysno, res),
+    }
+    #[cfg(feature = "strace")]
+    if let Some(trace) = trace {
+        trace.exit(&res);
+    }
+    let ans = match res {
+        Ok(v) => v as isize,
+        Err(e) => -e.code() as isize,
     };
-    time_stat_from_kernel_to_user();
     info!("syscall return: {}", ans);
     ans
 }
@@ -0,0 +1,382 @@
+//! An strace-like tracer of the syscalls made by user tasks.
+//!
+//! Each traced syscall is printed as one line with the task ID, the syscall
+//! name, the decoded arguments and the result, e.g.
+//!
+//! ```text
+//! [strace] 5 openat(AT_FDCWD, "./text.txt", O_RDONLY|O_CLOEXEC, 0o0) = -1 ENOENT (No such file or directory)
+//! ```
+//!
+//! Tracing is toggled per task with `prctl(PR_SET_STRACE, on)`, and inherited
+//! by the child tasks. All the user tasks are traced from the start if the
+//! kernel is built with `AX_STRACE=1`.
+
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use alloc::{format, string::String, vec::Vec};
+use arceos_posix_api::AT_FDCWD;
+use axerrno::{LinuxError, LinuxResult};
+use axhal::{
+    arch::TrapFrame,
+    paging::MappingFlags,
+    time::{NANOS_PER_SEC, monotonic_time_nanos},
+};
+use axstd::println;
+use axtask::{TaskExtRef, current};
+use memory_addr::{MemoryAddr, PAGE_SIZE_4K, VirtAddr};
+use syscalls::Sysno;
+
+use super::{MmapFlags, MmapProt};
+
+/// The `prctl` option to enable (`arg2 != 0`) or disable (`arg2 == 0`) the
+/// tracing of the calling task.
+pub const PR_SET_STRACE: i32 = 0x5354_5243;
+
+/// The `prctl` option to get whether the calling task is traced.
+pub const PR_GET_STRACE: i32 = 0x5354_5244;
+
+/// The maximum number of lines printed per second, the others are dropped.
+const MAX_LINES_PER_SEC: usize = 200;
+
+/// The maximum length of the strings shown in the arguments.
+const MAX_STR_LEN: usize = 64;
+
+/// The names of the flags of `open`, besides the access mode.
+const OPEN_FLAGS: &[(usize, &str)] = &[
+    (0o100, "O_CREAT"),
+    (0o200, "O_EXCL"),
+    (0o400, "O_NOCTTY"),
+    (0o1000, "O_TRUNC"),
+    (0o2000, "O_APPEND"),
+    (0o4000, "O_NONBLOCK"),
+    (0o10000, "O_DSYNC"),
+    (0o20000, "O_ASYNC"),
+    (0o40000, "O_DIRECT"),
+    (0o100000, "O_LARGEFILE"),
+    (0o200000, "O_DIRECTORY"),
+    (0o400000, "O_NOFOLLOW"),
+    (0o1000000, "O_NOATIME"),
+    (0o2000000, "O_CLOEXEC"),
+    (0o10000000, "O_PATH"),
+];
+
+/// Returns whether the user tasks are traced from the start.
+pub fn enabled_by_default() -> bool {
+    matches!(option_env!("AX_STRACE"), Some(s) if s != "0")
+}
+
+/// The kind of a syscall argument, which decides how it is shown.
+#[derive(Clone, Copy)]
+enum Arg {
+    /// An address or a bit mask, shown in hex.
+    Hex,
+    /// A signed integer.
+    Int,
+    /// An unsigned integer, such as a size.
+    UInt,
+    /// A file descriptor, or `AT_FDCWD`.
+    Fd,
+    /// A NUL-terminated string in the user space, such as a path.
+    Str,
+    /// A file mode, shown in octal.
+    Mode,
+    /// The flags of `open`.
+    OpenFlags,
+    /// The protection of `mmap`.
+    MmapProt,
+    /// The flags of `mmap`.
+    MmapFlags,
+}
+
+/// Returns the kinds of the arguments of `sysno`, or `None` if the syscall
+/// is not decoded.
+fn arg_kinds(sysno: Sysno) -> Option<&'static [Arg]> {
+    use Arg::*;
+    Some(match sysno {
+        Sysno::read | Sysno::write | Sysno::readv | Sysno::writev | Sysno::getdents64 => {
+            &[Fd, Hex, UInt]
+        }
+        Sysno::openat => &[Fd, Str, OpenFlags, Mode],
+        Sysno::close | Sysno::dup => &[Fd],
+        Sysno::dup3 => &[Fd, Fd, Hex],
+        Sysno::ioctl => &[Fd, Hex, Hex],
+        Sysno::pipe2 => &[Hex, Hex],
+        Sysno::ppoll => &[Hex, UInt, Hex, Hex, UInt],
+        Sysno::pselect6 => &[Int, Hex, Hex, Hex, Hex, Hex],
+        Sysno::fstat => &[Fd, Hex],
+        #[cfg(target_arch = "x86_64")]
+        Sysno::newfstatat => &[Fd, Str, Hex, Hex],
+        #[cfg(not(target_arch = "x86_64"))]
+        Sysno::fstatat => &[Fd, Str, Hex, Hex],
+        Sysno::statx => &[Fd, Str, Hex, Hex, Hex],
+        Sysno::chdir => &[Str],
+        Sysno::mkdirat => &[Fd, Str, Mode],
+        Sysno::unlinkat => &[Fd, Str, Hex],
+        Sysno::renameat => &[Fd, Str, Fd, Str],
+        Sysno::renameat2 => &[Fd, Str, Fd, Str, Hex],
+        Sysno::faccessat => &[Fd, Str, Hex],
+        Sysno::linkat => &[Fd, Str, Fd, Str, Hex],
+        Sysno::getcwd => &[Hex, UInt],
+        Sysno::mmap => &[Hex, UInt, MmapProt, MmapFlags, Fd, Hex],
+        Sysno::munmap => &[Hex, UInt],
+        Sysno::brk => &[Hex],
+        Sysno::execve => &[Str, Hex, Hex],
+        Sysno::clone => &[Hex, Hex, Hex, Hex, Hex],
+        Sysno::wait4 => &[Int, Hex, Hex],
+        Sysno::exit | Sysno::exit_group => &[Int],
+        Sysno::getpid | Sysno::getppid | Sysno::gettid | Sysno::sched_yield => &[],
+        Sysno::getpgid | Sysno::getsid => &[Int],
+        Sysno::setpgid => &[Int, Int],
+        Sysno::set_tid_address | Sysno::uname | Sysno::times | Sysno::gettimeofday => &[Hex],
+        Sysno::nanosleep | Sysno::getcpu => &[Hex, Hex],
+        Sysno::clock_gettime => &[Int, Hex],
+        Sysno::prlimit64 => &[Int, Int, Hex, Hex],
+        Sysno::getrlimit | Sysno::setrlimit | Sysno::getrusage => &[Int, Hex],
+        Sysno::prctl => &[Hex, Hex],
+        _ => return None,
+    })
+}
+
+/// Copy a NUL-terminated string from the user space without faulting, or
+/// return `None` if its first byte is not readable.
+///
+/// Only the first [`MAX_STR_LEN`] bytes are copied, and the returned flag
+/// tells whether the string is truncated.
+fn read_user_str(ptr: usize) -> Option<(String, bool)> {
+    let curr = current();
+    let aspace = curr.task_ext().aspace.lock();
+    let mut bytes = Vec::new();
+    let mut addr = VirtAddr::from(ptr);
+    while bytes.len() < MAX_STR_LEN {
+        // Read a page only if it is present and readable, a lazy page is
+        // not populated for the tracer.
+        match aspace.page_table().query(addr) {
+            Ok((_, flags, _)) if flags.contains(MappingFlags::READ) => {}
+            _ if bytes.is_empty() => return None,
+            _ => break,
+        }
+        let mut chunk = [0u8; MAX_STR_LEN];
+        let len = (PAGE_SIZE_4K - addr.align_offset_4k()).min(MAX_STR_LEN - bytes.len());
+        if aspace.read(addr, &mut chunk[..len]).is_err() {
+            break;
+        }
+        if let Some(end) = chunk[..len].iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&chunk[..end]);
+            return Some((String::from_utf8_lossy(&bytes).into_owned(), false));
+        }
+        bytes.extend_from_slice(&chunk[..len]);
+        addr += len;
+    }
+    Some((String::from_utf8_lossy(&bytes).into_owned(), true))
+}
+
+/// Append the names of the bits set in `value` to `out`, separated by `|`.
+///
+/// Unknown bits are shown in hex, and `0` is shown if nothing is set.
+fn fmt_flags<'a>(out: &mut String, value: usize, names: impl Iterator<Item = (usize, &'a str)>) {
+    let mut rest = value;
+    let mut first = true;
+    for (bit, name) in names {
+        if rest & bit != 0 {
+            if !first {
+                out.push('|');
+            }
+            out.push_str(name);
+            rest &= !bit;
+            first = false;
+        }
+    }
+    if rest != 0 || first {
+        if !first {
+            out.push('|');
+        }
+        let _ = write!(out, "{:#x}", rest);
+    }
+}
+
+/// Append the decoded argument `value` of `kind` to `out`.
+fn fmt_arg(out: &mut String, kind: Arg, value: usize) {
+    let _ = match kind {
+        Arg::Hex => write!(out, "{:#x}", value),
+        Arg::Int => write!(out, "{}", value as i32),
+        Arg::UInt => write!(out, "{}", value),
+        // The `int` argument may not be sign-extended in the register.
+        Arg::Fd if value as i32 as isize == AT_FDCWD => write!(out, "AT_FDCWD"),
+        Arg::Fd => write!(out, "{}", value as i32),
+        Arg::Str if value == 0 => write!(out, "NULL"),
+        Arg::Str => match read_user_str(value) {
+            Some((s, false)) => write!(out, "{:?}", s),
+            Some((s, true)) => write!(out, "{:?}...", s),
+            None => write!(out, "{:#x}", value),
+        },
+        Arg::Mode => write!(out, "{:#o}", value),
+        Arg::OpenFlags => {
+            out.push_str(match value & 0o3 {
+                0 => "O_RDONLY",
+                1 => "O_WRONLY",
+                2 => "O_RDWR",
+                _ => "O_ACCMODE",
+            });
+            if value & !0o3 != 0 {
+                out.push('|');
+                fmt_flags(out, value & !0o3, OPEN_FLAGS.iter().copied());
+            }
+            Ok(())
+        }
+        Arg::MmapProt if value == 0 => write!(out, "PROT_NONE"),
+        Arg::MmapProt => {
+            let names = MmapProt::all()
+                .iter_names()
+                .map(|(name, f)| (f.bits() as usize, name));
+            fmt_flags(out, value, names);
+            Ok(())
+        }
+        Arg::MmapFlags => {
+            let names = MmapFlags::all()
+                .iter_names()
+                .map(|(name, f)| (f.bits() as usize, name));
+            fmt_flags(out, value, names);
+            Ok(())
+        }
+    };
+}
+
+/// The second of the current rate limiting window.
+static WINDOW: AtomicU64 = AtomicU64::new(0);
+/// The number of lines printed in the current window.
+static LINES: AtomicUsize = AtomicUsize::new(0);
+/// The number of lines dropped in the current window.
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Print a trace line, unless [`MAX_LINES_PER_SEC`] lines have been printed
+/// in the current second.
+///
+/// The line is formatted in advance and printed with a single call, after the
+/// syscall has returned, so that no lock taken by the syscall (e.g. the one of
+/// the console by `write`) is held here.
+fn print_line(line: &str) {
+    let now = monotonic_time_nanos() / NANOS_PER_SEC;
+    let window = WINDOW.load(Ordering::Relaxed);
+    if now != window
+        && WINDOW
+            .compare_exchange(window, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    {
+        LINES.store(0, Ordering::Relaxed);
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            println!("[strace] {} lines dropped", dropped);
+        }
+    }
+    if LINES.fetch_add(1, Ordering::Relaxed) < MAX_LINES_PER_SEC {
+        println!("{}", line);
+    } else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A syscall being traced, which is printed when it returns.
+pub struct SyscallTrace {
+    /// The line without the result.
+    line: String,
+    sysno: Sysno,
+}
+
+impl SyscallTrace {
+    /// Start tracing a syscall with the arguments in `tf`, or return `None` if
+    /// the current task is not traced.
+    ///
+    /// The arguments are decoded now, since the syscall may change the memory
+    /// they point to. Syscalls that never return are printed immediately.
+    pub fn enter(sysno: Sysno, tf: &TrapFrame) -> Option<Self> {
+        let curr = current();
+        if !curr.task_ext().strace.load(Ordering::Relaxed) {
+            return None;
+        }
+        let args = [
+            tf.arg0(),
+            tf.arg1(),
+            tf.arg2(),
+            tf.arg3(),
+            tf.arg4(),
+            tf.arg5(),
+        ];
+        let mut line = format!("[strace] {} {}(", curr.task_ext().proc_id, sysno.name());
+        match arg_kinds(sysno) {
+            Some(kinds) => {
+                for (i, (&kind, &value)) in kinds.iter().zip(args.iter()).enumerate() {
+                    if i > 0 {
+                        line.push_str(", ");
+                    }
+                    fmt_arg(&mut line, kind, value);
+                }
+            }
+            None => {
+                let args = args.iter().map(|arg| format!("{:#x}", arg));
+                line.push_str(&args.collect::<Vec<_>>().join(", "));
+            }
+        }
+        line.push(')');
+
+        if matches!(sysno, Sysno::exit | Sysno::exit_group) {
+            line.push_str(" = ?");
+            print_line(&line);
+            return None;
+        }
+        Some(Self { line, sysno })
+    }
+
+    /// Finish tracing the syscall with its result.
+    pub fn exit(mut self, res: &LinuxResult<usize>) {
+        let _ = match res {
+            Ok(ret) if matches!(self.sysno, Sysno::mmap | Sysno::brk) => {
+                write!(self.line, " = {:#x}", ret)
+            }
+            Ok(ret) => write!(self.line, " = {}", *ret as isize),
+            Err(err) => write!(self.line, " = -1 {:?} ({})", err, err.as_str()),
+        };
+        print_line(&self.line);
+    }
+}
+
+/// Enable or disable the tracing of the calling task, see [`PR_SET_STRACE`]
+/// and [`PR_GET_STRACE`]. Other options are not supported.
+pub(crate) fn sys_prctl(option: i32, arg2: usize) -> LinuxResult<usize> {
+    let curr = current();
+    match option {
+        PR_SET_STRACE => {
+            curr.task_ext().strace.store(arg2 != 0, Ordering::Relaxed);
+            Ok(0)
+        }
+        PR_GET_STRACE => Ok(curr.task_ext().strace.load(Ordering::Relaxed) as usize),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(kind: Arg, value: usize) -> String {
+        let mut out = String::new();
+        fmt_arg(&mut out, kind, value);
+        out
+    }
+
+    #[test]
+    fn fmt_args() {
+        assert_eq!(fmt(Arg::Fd, AT_FDCWD as usize), "AT_FDCWD");
+        assert_eq!(fmt(Arg::Fd, AT_FDCWD as u32 as usize), "AT_FDCWD");
+        assert_eq!(fmt(Arg::Fd, 3), "3");
+        assert_eq!(fmt(Arg::Int, -1i32 as usize), "-1");
+        assert_eq!(fmt(Arg::Hex, 0x1000), "0x1000");
+        assert_eq!(fmt(Arg::Mode, 0o644), "0o644");
+        assert_eq!(fmt(Arg::Str, 0), "NULL");
+        assert_eq!(fmt(Arg::OpenFlags, 0o2000000), "O_RDONLY|O_CLOEXEC");
+        assert_eq!(fmt(Arg::OpenFlags, 0o1101), "O_WRONLY|O_CREAT|O_TRUNC");
+        assert_eq!(fmt(Arg::OpenFlags, 0o20000002), "O_RDWR|0x400000");
+    }
+}
@@ -1,5 +1,7 @@
+mod rlimit;
 mod schedule;
 mod thread;
 
+pub(crate) use self::rlimit::*;
 pub(crate) use self::schedule::*;
 pub(crate) use self::thread::*;
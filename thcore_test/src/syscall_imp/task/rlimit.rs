@@ -0,0 +1,40 @@
+use axerrno::{LinuxError, LinuxResult};
+use axtask::{TaskExtRef, current};
+
+use crate::ctypes::{RLimit, RLimitResource};
+
+/// Get and/or set the limit of `resource` of the process `pid`.
+///
+/// Only the calling process can be targeted, i.e., `pid` is 0 or its own ID.
+/// Since there is no privileged process, raising a hard limit is not allowed.
+pub(crate) fn sys_prlimit64(
+    pid: i32,
+    resource: u32,
+    new_limit: *const RLimit,
+    old_limit: *mut RLimit,
+) -> LinuxResult<usize> {
+    let curr = current();
+    if pid != 0 && pid as usize != curr.task_ext().proc_id {
+        return Err(LinuxError::ESRCH);
+    }
+    let resource = RLimitResource::try_from(resource).map_err(|_| LinuxError::EINVAL)?;
+
+    let old = if new_limit.is_null() {
+        curr.task_ext().get_rlimit(resource)
+    } else {
+        curr.task_ext()
+            .set_rlimit(resource, unsafe { new_limit.read() })?
+    };
+    if !old_limit.is_null() {
+        unsafe { old_limit.write(old) };
+    }
+    Ok(0)
+}
+
+pub(crate) fn sys_getrlimit(resource: u32, rlim: *mut RLimit) -> LinuxResult<usize> {
+    sys_prlimit64(0, resource, core::ptr::null(), rlim)
+}
+
+pub(crate) fn sys_setrlimit(resource: u32, rlim: *const RLimit) -> LinuxResult<usize> {
+    sys_prlimit64(0, resource, rlim, core::ptr::null_mut())
+}
@@ -1,12 +1,49 @@
 use arceos_posix_api as api;
+use axerrno::{LinuxError, LinuxResult};
+use axtask::{AxCpuMask, TaskExtRef, current};
 
-pub(crate) fn sys_sched_yield() -> i32 {
-    api::sys_sched_yield()
+use crate::syscall_imp::posix_result;
+
+pub(crate) fn sys_sched_yield() -> LinuxResult<usize> {
+    posix_result(api::sys_sched_yield() as _)
 }
 
 pub(crate) fn sys_nanosleep(
     req: *const api::ctypes::timespec,
     rem: *mut api::ctypes::timespec,
-) -> i32 {
-    unsafe { api::sys_nanosleep(req, rem) }
+) -> LinuxResult<usize> {
+    posix_result(unsafe { api::sys_nanosleep(req, rem) } as _)
+}
+
+/// Set the CPU affinity of the current process, and migrate it to one of the
+/// CPUs in `mask` if needed.
+///
+/// Only the current process (`pid` 0 or its own ID) is supported. The mask
+/// must contain at least one CPU, and none beyond the CPUs brought up at boot.
+pub(crate) fn sys_sched_setaffinity(pid: i32, size: usize, mask: *const u8) -> LinuxResult<usize> {
+    if pid != 0 && pid as usize != current().task_ext().proc_id {
+        return Err(LinuxError::ESRCH);
+    }
+    if mask.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(mask, size) };
+    let cpumask = AxCpuMask::try_from_bytes(bytes).map_err(|_| LinuxError::EINVAL)?;
+    if !axtask::set_current_affinity(cpumask) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(0)
+}
+
+/// Get the CPU and the NUMA node that the current task is running on.
+///
+/// There is only one NUMA node, so the node is always 0.
+pub(crate) fn sys_getcpu(cpu: *mut u32, node: *mut u32) -> LinuxResult<usize> {
+    if !cpu.is_null() {
+        unsafe { cpu.write(axhal::cpu::this_cpu_id() as u32) };
+    }
+    if !node.is_null() {
+        unsafe { node.write(0) };
+    }
+    Ok(0)
 }
@@ -1,14 +1,14 @@
-use core::ffi::{c_char, c_int};
+use core::ffi::c_char;
 
 use alloc::string::String;
-use axerrno::LinuxError;
-use axtask::{TaskExtRef, current, yield_now};
+use axerrno::{LinuxError, LinuxResult};
+use axtask::{AxTaskRef, TaskExtRef, current, yield_now};
 use num_enum::TryFromPrimitive;
 use axstd::println;
 use crate::{
     ctypes::{WaitFlags, WaitStatus},
-    syscall_body,
-    task::wait_pid,
+    pid,
+    task::{SpawnError, exit_current, wait_pid},
 };
 
 /// ARCH_PRCTL codes
@@ -32,16 +32,83 @@ enum ArchPrctlCode {
     SetCpuid = 0x1012,
 }
 
-pub(crate) fn sys_getpid() -> i32 {
-    syscall_body!(sys_getpid, {
-        Ok(axtask::current().task_ext().proc_id as c_int)
-    })
+pub(crate) fn sys_getpid() -> LinuxResult<usize> {
+    Ok(axtask::current().task_ext().proc_id)
 }
 
-pub(crate) fn sys_getppid() -> i32 {
-    syscall_body!(sys_getppid, {
-        Ok(axtask::current().task_ext().get_parent() as c_int)
-    })
+pub(crate) fn sys_getppid() -> LinuxResult<usize> {
+    Ok(axtask::current().task_ext().get_parent() as usize)
+}
+
+/// Get the thread ID, which is the same as the PID since every process has
+/// only one thread for now.
+pub(crate) fn sys_gettid() -> LinuxResult<usize> {
+    Ok(axtask::current().task_ext().proc_id)
+}
+
+/// Find the process of `pid`, or the current process if `pid` is 0.
+fn find_process(pid: i32) -> LinuxResult<AxTaskRef> {
+    match pid {
+        0 => Ok(current().as_task_ref().clone()),
+        pid if pid > 0 => pid::find_task(pid as usize).ok_or(LinuxError::ESRCH),
+        _ => Err(LinuxError::ESRCH),
+    }
+}
+
+pub(crate) fn sys_getpgid(pid: i32) -> LinuxResult<usize> {
+    Ok(find_process(pid)?.task_ext().get_pgid())
+}
+
+pub(crate) fn sys_getsid(pid: i32) -> LinuxResult<usize> {
+    Ok(find_process(pid)?.task_ext().get_sid())
+}
+
+/// Set the process group of `pid` to `pgid`, where 0 means the current
+/// process and the PID of the target process respectively.
+///
+/// The rules of Linux are followed, except that the `EACCES` check for a
+/// child that has called `execve` is not implemented:
+///
+/// - the target must be the caller or one of its children (`ESRCH`);
+/// - the target must not be a session leader, and must be in the session of
+///   the caller (`EPERM`);
+/// - the process group, if not created by this call, must exist in the same
+///   session (`EPERM`).
+pub(crate) fn sys_setpgid(pid: i32, pgid: i32) -> LinuxResult<usize> {
+    if pid < 0 || pgid < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let curr = current();
+    let curr_ext = curr.task_ext();
+    let target = match pid as usize {
+        0 => curr.as_task_ref().clone(),
+        pid if pid == curr_ext.proc_id => curr.as_task_ref().clone(),
+        pid => curr_ext
+            .children
+            .lock()
+            .iter()
+            .find(|child| child.task_ext().proc_id == pid)
+            .cloned()
+            .ok_or(LinuxError::ESRCH)?,
+    };
+    let target_ext = target.task_ext();
+    let sid = target_ext.get_sid();
+    if sid == target_ext.proc_id || sid != curr_ext.get_sid() {
+        return Err(LinuxError::EPERM);
+    }
+    let pgid = match pgid as usize {
+        0 => target_ext.proc_id,
+        pgid => pgid,
+    };
+    if pgid != target_ext.proc_id
+        && !pid::all_tasks()
+            .iter()
+            .any(|task| task.task_ext().get_pgid() == pgid && task.task_ext().get_sid() == sid)
+    {
+        return Err(LinuxError::EPERM);
+    }
+    target_ext.set_pgid(pgid);
+    Ok(0)
 }
 
 pub(crate) fn sys_exit(status: i32) -> ! {
@@ -55,58 +122,53 @@ pub(crate) fn sys_exit(status: i32) -> ! {
         }
         // TODO: wake up threads, which are blocked by futex, and waiting for the address pointed by clear_child_tid
     }
-    axtask::exit(status);
+    exit_current(status);
 }
 
 pub(crate) fn sys_exit_group(status: i32) -> ! {
     warn!("Temporarily replace sys_exit_group with sys_exit");
-    axtask::exit(status);
+    exit_current(status);
 }
 
 /// To set the clear_child_tid field in the task extended data.
 ///
 /// The set_tid_address() always succeeds
-pub(crate) fn sys_set_tid_address(tid_ptd: *const i32) -> isize {
-    syscall_body!(sys_set_tid_address, {
-        let curr = current();
-        curr.task_ext().set_clear_child_tid(tid_ptd as _);
-        Ok(curr.id().as_u64() as isize)
-    })
+pub(crate) fn sys_set_tid_address(tid_ptd: *const i32) -> LinuxResult<usize> {
+    let curr = current();
+    curr.task_ext().set_clear_child_tid(tid_ptd as _);
+    Ok(curr.task_ext().proc_id)
 }
 
 #[cfg(target_arch = "x86_64")]
-pub(crate) fn sys_arch_prctl(code: i32, addr: u64) -> isize {
-    use axerrno::LinuxError;
-    syscall_body!(sys_arch_prctl, {
-        match ArchPrctlCode::try_from(code) {
-            // TODO: check the legality of the address
-            Ok(ArchPrctlCode::SetFs) => {
-                unsafe {
-                    axhal::arch::write_thread_pointer(addr as usize);
-                }
-                Ok(0)
+pub(crate) fn sys_arch_prctl(code: i32, addr: u64) -> LinuxResult<usize> {
+    match ArchPrctlCode::try_from(code) {
+        // TODO: check the legality of the address
+        Ok(ArchPrctlCode::SetFs) => {
+            unsafe {
+                axhal::arch::write_thread_pointer(addr as usize);
             }
-            Ok(ArchPrctlCode::GetFs) => {
-                unsafe {
-                    *(addr as *mut u64) = axhal::arch::read_thread_pointer() as u64;
-                }
-                Ok(0)
+            Ok(0)
+        }
+        Ok(ArchPrctlCode::GetFs) => {
+            unsafe {
+                *(addr as *mut u64) = axhal::arch::read_thread_pointer() as u64;
             }
-            Ok(ArchPrctlCode::SetGs) => {
-                unsafe {
-                    x86::msr::wrmsr(x86::msr::IA32_KERNEL_GSBASE, addr);
-                }
-                Ok(0)
+            Ok(0)
+        }
+        Ok(ArchPrctlCode::SetGs) => {
+            unsafe {
+                x86::msr::wrmsr(x86::msr::IA32_KERNEL_GSBASE, addr);
             }
-            Ok(ArchPrctlCode::GetGs) => {
-                unsafe {
-                    *(addr as *mut u64) = x86::msr::rdmsr(x86::msr::IA32_KERNEL_GSBASE);
-                }
-                Ok(0)
+            Ok(0)
+        }
+        Ok(ArchPrctlCode::GetGs) => {
+            unsafe {
+                *(addr as *mut u64) = x86::msr::rdmsr(x86::msr::IA32_KERNEL_GSBASE);
             }
-            _ => Err(LinuxError::ENOSYS),
+            Ok(0)
         }
-    })
+        _ => Err(LinuxError::ENOSYS),
+    }
 }
 
 pub(crate) fn sys_clone(
@@ -115,87 +177,88 @@ pub(crate) fn sys_clone(
     ptid: usize,
     arg3: usize,
     arg4: usize,
-) -> isize {
-    syscall_body!(sys_clone, {
-        let tls = arg3;
-        let ctid = arg4;
-
-        let stack = if user_stack == 0 {
-            None
-        } else {
-            Some(user_stack)
-        };
-
-        let curr_task = current();
-        
-        if let Ok(new_task_id) = curr_task
-            .task_ext()
-            .clone_task(flags, stack, ptid, tls, ctid)
-        {   
+) -> LinuxResult<usize> {
+    let tls = arg3;
+    let ctid = arg4;
+
+    let stack = if user_stack == 0 {
+        None
+    } else {
+        Some(user_stack)
+    };
+
+    let curr_task = current();
+
+    match curr_task
+        .task_ext()
+        .clone_task(flags, stack, ptid, tls, ctid)
+    {
+        Ok(new_task_id) => {
             info!("clone: new_task_id: {:?}", new_task_id);
-            Ok(new_task_id as isize)
-        } else{
-            Err(LinuxError::ENOMEM)
+            Ok(new_task_id as usize)
         }
-    })
+        Err(SpawnError::NoPid) => Err(LinuxError::EAGAIN),
+        Err(_) => Err(LinuxError::ENOMEM),
+    }
 }
 
-pub(crate) fn sys_wait4(pid: i32, exit_code_ptr: *mut i32, option: u32) -> isize {
+pub(crate) fn sys_wait4(pid: i32, exit_code_ptr: *mut i32, option: u32) -> LinuxResult<usize> {
     let option_flag = WaitFlags::from_bits(option).unwrap();
-    syscall_body!(sys_wait4, {
-        loop {
-            let answer = wait_pid(pid, exit_code_ptr);
-            match answer {
-                Ok(pid) => {
-                    return Ok(pid as isize);
+    loop {
+        let answer = wait_pid(pid, exit_code_ptr);
+        match answer {
+            Ok(pid) => {
+                return Ok(pid as usize);
+            }
+            Err(status) => match status {
+                WaitStatus::NotExist => {
+                    return Err(LinuxError::ECHILD);
                 }
-                Err(status) => match status {
-                    WaitStatus::NotExist => {
-                        return Err(LinuxError::ECHILD);
-                    }
-                    WaitStatus::Running => {
-                        if option_flag.contains(WaitFlags::WNOHANG) {
-                            return Ok(0);
-                        } else {
-                            yield_now();
-                        }
+                WaitStatus::Running => {
+                    if option_flag.contains(WaitFlags::WNOHANG) {
+                        return Ok(0);
+                    } else {
+                        yield_now();
+                        crate::task::exit_if_killed();
                     }
-                    _ => {
-                        panic!("Shouldn't reach here!");
-                    }
-                },
-            }
+                }
+                _ => {
+                    panic!("Shouldn't reach here!");
+                }
+            },
         }
-    })
+    }
 }
 
-pub fn sys_execve(path: *const c_char, argv: *const usize, envp: *const usize) -> isize {
-    syscall_body!(sys_execve, {
-        let path_str = arceos_posix_api::char_ptr_to_str(path)?;
-        // let dir = axfs::api::current_dir()?;
-        // let path_str = dir + path_str;
-        info!("execve: {:?}", path_str);
-        // if path_str.split('/').filter(|s| !s.is_empty()).count() > 1 {
-        //     info!("Multi-level directories are not supported");
-        //     return Err::<isize, _>(LinuxError::EINVAL);
-        // }
-
-        let argv_valid = unsafe { argv.is_null() || *argv == 0 };
-        let envp_valid = unsafe { envp.is_null() || *envp == 0 };
-
-        if !argv_valid {
-            info!("argv is not supported");
-        }
+pub fn sys_execve(
+    path: *const c_char,
+    argv: *const usize,
+    envp: *const usize,
+) -> LinuxResult<usize> {
+    let path_str = arceos_posix_api::char_ptr_to_str(path)?;
+    // let dir = axfs::api::current_dir()?;
+    // let path_str = dir + path_str;
+    info!("execve: {:?}", path_str);
+    // if path_str.split('/').filter(|s| !s.is_empty()).count() > 1 {
+    //     info!("Multi-level directories are not supported");
+    //     return Err::<isize, _>(LinuxError::EINVAL);
+    // }
 
-        if !envp_valid {
-            info!("envp is not supported");
-        }
+    let argv_valid = unsafe { argv.is_null() || *argv == 0 };
+    let envp_valid = unsafe { envp.is_null() || *envp == 0 };
 
-        if let Err(e) = crate::task::exec(&path_str) {
-            error!("Failed to exec: {:?}", e);
-            return Err::<isize, _>(LinuxError::ENOSYS);
-        }
+    if !argv_valid {
+        info!("argv is not supported");
+    }
+
+    if !envp_valid {
+        info!("envp is not supported");
+    }
+
+    if let Err(e) = crate::task::exec(&path_str) {
+        error!("Failed to exec: {:?}", e);
+        return Err(LinuxError::ENOSYS);
+    }
 
-        unreachable!("execve should never return");
-    })
+    unreachable!("execve should never return");
 }
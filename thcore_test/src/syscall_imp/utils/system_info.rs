@@ -1,3 +1,5 @@
+use axerrno::{LinuxError, LinuxResult};
+
 #[repr(C)]
 pub struct UtsName {
     /// sysname
@@ -35,8 +37,11 @@ impl UtsName {
     }
 }
 
-pub fn sys_uname(name: *mut UtsName) -> i64 {
+pub fn sys_uname(name: *mut UtsName) -> LinuxResult<usize> {
+    if name.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
     let utsname = unsafe { &mut *name };
     *utsname = UtsName::default();
-    0
+    Ok(0)
 }
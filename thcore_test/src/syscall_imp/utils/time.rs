@@ -1,29 +1,88 @@
-use core::ffi::c_int;
+use core::time::Duration;
 
 use arceos_posix_api::{self as api, ctypes::timeval};
-use axhal::time::{monotonic_time_nanos, nanos_to_ticks};
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::monotonic_time;
+use axtask::{TaskExtRef, current};
+use memory_addr::PAGE_SIZE_4K;
 
-use crate::{ctypes::Tms, syscall_body, task::time_stat_output};
+use crate::{
+    ctypes::{Rusage, Tms},
+    syscall_imp::posix_result,
+    task::cpu_times,
+};
 
-pub(crate) fn sys_clock_gettime(clock_id: i32, tp: *mut api::ctypes::timespec) -> i32 {
-    unsafe { api::sys_clock_gettime(clock_id, tp) }
+/// The frequency of `clock_t` ticks, as advertised in `AT_CLKTCK`.
+const CLOCKS_PER_SEC: u128 = 100;
+
+/// `getrusage` of the calling process.
+const RUSAGE_SELF: i32 = 0;
+/// `getrusage` of the reaped children of the calling process.
+const RUSAGE_CHILDREN: i32 = -1;
+/// `getrusage` of the calling thread.
+const RUSAGE_THREAD: i32 = 1;
+
+/// Convert `duration` to `clock_t` ticks.
+fn clock_ticks(duration: Duration) -> usize {
+    (duration.as_nanos() * CLOCKS_PER_SEC / 1_000_000_000) as usize
+}
+
+pub(crate) fn sys_clock_gettime(
+    clock_id: i32,
+    tp: *mut api::ctypes::timespec,
+) -> LinuxResult<usize> {
+    posix_result(unsafe { api::sys_clock_gettime(clock_id, tp) } as _)
 }
 
-pub(crate) fn sys_get_time_of_day(ts: *mut timeval) -> c_int {
-    unsafe { api::sys_get_time_of_day(ts) }
+pub(crate) fn sys_get_time_of_day(ts: *mut timeval) -> LinuxResult<usize> {
+    posix_result(unsafe { api::sys_get_time_of_day(ts) } as _)
 }
 
-pub fn sys_times(tms: *mut Tms) -> isize {
-    syscall_body!(sys_times, {
-        let (_, utime_us, _, stime_us) = time_stat_output();
+/// Get the CPU times of the calling process and its reaped children in `tms`
+/// if it's not null, and return the clock ticks since boot.
+pub fn sys_times(tms: *mut Tms) -> LinuxResult<usize> {
+    let (times, children_times) = cpu_times(&current());
+    if !tms.is_null() {
         unsafe {
             *tms = Tms {
-                tms_utime: utime_us,
-                tms_stime: stime_us,
-                tms_cutime: utime_us,
-                tms_cstime: stime_us,
+                tms_utime: clock_ticks(times.utime),
+                tms_stime: clock_ticks(times.stime),
+                tms_cutime: clock_ticks(children_times.utime),
+                tms_cstime: clock_ticks(children_times.stime),
+            }
+        }
+    }
+    Ok(clock_ticks(monotonic_time()))
+}
+
+/// Get the resource usage of `who` in `usage`.
+///
+/// A process has only one thread, so `RUSAGE_THREAD` is the same as
+/// `RUSAGE_SELF`. The maximum resident set size is the current one of the
+/// calling process, and is not reported for the children.
+pub(crate) fn sys_getrusage(who: i32, usage: *mut Rusage) -> LinuxResult<usize> {
+    if usage.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let curr = current();
+    let (times, children_times) = cpu_times(&curr);
+    let rusage = match who {
+        RUSAGE_SELF | RUSAGE_THREAD => {
+            let pages = curr.task_ext().aspace.lock().resident_pages();
+            Rusage {
+                ru_utime: times.utime.into(),
+                ru_stime: times.stime.into(),
+                ru_maxrss: (pages * PAGE_SIZE_4K / 1024) as i64,
+                ..Default::default()
             }
         }
-        Ok(nanos_to_ticks(monotonic_time_nanos()) as isize)
-    })
+        RUSAGE_CHILDREN => Rusage {
+            ru_utime: children_times.utime.into(),
+            ru_stime: children_times.stime.into(),
+            ..Default::default()
+        },
+        _ => return Err(LinuxError::EINVAL),
+    };
+    unsafe { *usage = rusage };
+    Ok(0)
 }
@@ -1,24 +1,28 @@
 use alloc::{string::ToString, sync::Arc, vec, vec::Vec};
-use arceos_posix_api::FD_TABLE;
-use axerrno::{AxError, AxResult};
+use arceos_posix_api::{AX_FILE_LIMIT, FD_LIMIT, FD_TABLE};
+use axerrno::{AxError, AxResult, LinuxError, LinuxResult};
 use axfs::{CURRENT_DIR, CURRENT_DIR_PATH};
 use axstd::println;
 use core::{
     alloc::Layout,
     cell::UnsafeCell,
-    sync::atomic::{AtomicU64, Ordering},
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
 };
 use spin::Once;
 
-use crate::ctypes::{CloneFlags, TimeStat, WaitStatus};
+use crate::ctypes::{CloneFlags, RLIM_NLIMITS, RLimit, RLimitResource, TimeStat, WaitStatus};
+use crate::pid::{self, INIT_PID};
 use axhal::{
     arch::{TrapFrame, UspaceContext},
-    time::{NANOS_PER_MICROS, NANOS_PER_SEC, monotonic_time_nanos},
+    time::monotonic_time_nanos,
+    trap::{EXCEPTION, ExceptionKind, USER_ENTRY, USER_RETURN, register_trap_handler},
 };
 use axmm::AddrSpace;
 use axns::{AxNamespace, AxNamespaceIf};
 use axsync::Mutex;
-use axtask::{AxTaskRef, TaskExtRef, TaskInner, current};
+use axtask::{AxCpuMask, AxTaskRef, TaskExtRef, TaskInner, current};
+use memory_addr::VirtAddr;
 
 /// Task extended data for the monolithic kernel.
 pub struct TaskExt {
@@ -26,6 +30,10 @@ pub struct TaskExt {
     pub proc_id: usize,
     /// The parent process ID.
     pub parent_id: AtomicU64,
+    /// The process group ID.
+    pgid: AtomicUsize,
+    /// The session ID.
+    sid: AtomicUsize,
     /// children process
     pub children: Mutex<Vec<AxTaskRef>>,
     /// The clear thread tid field
@@ -46,9 +54,29 @@ pub struct TaskExt {
     pub heap_bottom: AtomicU64,
     /// The user heap top
     pub heap_top: AtomicU64,
+    /// The resource limits
+    rlimits: Mutex<[RLimit; RLIM_NLIMITS]>,
+    /// Whether the process has been killed by [`kill_session`]
+    killed: AtomicBool,
+    /// Whether the syscalls are traced
+    #[cfg(feature = "strace")]
+    pub strace: AtomicBool,
+}
+
+/// The resource limits of a newly created process.
+fn default_rlimits() -> [RLimit; RLIM_NLIMITS] {
+    let mut rlimits = [RLimit::INFINITY; RLIM_NLIMITS];
+    // The stack can't grow beyond the region reserved for it by the loader.
+    let stack_size = axconfig::plat::USER_STACK_MAX_SIZE as u64;
+    rlimits[RLimitResource::STACK as usize] = RLimit::new(stack_size, stack_size);
+    let nofile = AX_FILE_LIMIT as u64;
+    rlimits[RLimitResource::NOFILE as usize] = RLimit::new(nofile, nofile);
+    rlimits
 }
 
 impl TaskExt {
+    /// Create the task extended data of a process, which is the child of
+    /// init and the leader of a new session and process group.
     pub fn new(
         proc_id: usize,
         uctx: UspaceContext,
@@ -57,7 +85,9 @@ impl TaskExt {
     ) -> Self {
         Self {
             proc_id,
-            parent_id: AtomicU64::new(1),
+            parent_id: AtomicU64::new(INIT_PID as u64),
+            pgid: AtomicUsize::new(proc_id),
+            sid: AtomicUsize::new(proc_id),
             children: Mutex::new(Vec::new()),
             uctx,
             clear_child_tid: AtomicU64::new(0),
@@ -66,6 +96,10 @@ impl TaskExt {
             time: TimeStat::new().into(),
             heap_bottom: AtomicU64::new(heap_bottom),
             heap_top: AtomicU64::new(heap_bottom),
+            rlimits: Mutex::new(default_rlimits()),
+            killed: AtomicBool::new(false),
+            #[cfg(feature = "strace")]
+            strace: AtomicBool::new(crate::syscall_imp::strace::enabled_by_default()),
         }
     }
 
@@ -76,7 +110,7 @@ impl TaskExt {
         _ptid: usize,
         _tls: usize,
         _ctid: usize,
-    ) -> AxResult<u64> {
+    ) -> Result<u64, SpawnError> {
         
         let _clone_flags = CloneFlags::from_bits((flags & !0x3f) as u32).unwrap();
 
@@ -90,6 +124,7 @@ impl TaskExt {
                     curr.task_ext().uctx.get_sp(),
                     kstack_top,
                 );
+                time_stat_from_kernel_to_user();
                 unsafe { curr.task_ext().uctx.enter_uspace(kstack_top) };
             },
             current().id_name(),
@@ -98,10 +133,17 @@ impl TaskExt {
         
         let current_task = current();
         let mut current_aspace = current_task.task_ext().aspace.lock();
-        let new_aspace = current_aspace.clone_or_err()?;
+        let new_aspace = current_aspace
+            .clone_or_err()
+            .map_err(|_| SpawnError::NoMemory)?;
         new_task
             .ctx_mut()
             .set_page_table_root(new_aspace.page_table_root());
+        // The child resumes the user program with the floating-point registers
+        // of the parent, which the kernel leaves as they were in the user
+        // space, instead of zeros.
+        #[cfg(target_arch = "loongarch64")]
+        new_task.ctx_mut().fp_state.save();
         
         let trap_frame = read_trapframe_from_kstack(current_task.get_kernel_stack_top().unwrap());
         let mut new_uctx = UspaceContext::from(&trap_frame);
@@ -111,19 +153,32 @@ impl TaskExt {
         // Skip current instruction
         new_uctx.set_ip(new_uctx.get_ip() + 4);
         new_uctx.set_retval(0);
-        let return_id: u64 = new_task.id().as_u64();
+        let new_pid = pid::alloc_pid().ok_or(SpawnError::NoPid)?;
         let new_task_ext = TaskExt::new(
-            return_id as usize,
+            new_pid,
             new_uctx,
             Arc::new(Mutex::new(new_aspace)),
-            0,
+            current_task.task_ext().get_heap_bottom(),
         );
-        
+        new_task_ext.set_parent(current_task.task_ext().proc_id as u64);
+        new_task_ext.set_pgid(current_task.task_ext().get_pgid());
+        new_task_ext
+            .sid
+            .store(current_task.task_ext().get_sid(), Ordering::Release);
+        new_task_ext.set_heap_top(current_task.task_ext().get_heap_top());
+        *new_task_ext.rlimits.lock() = *current_task.task_ext().rlimits.lock();
+        #[cfg(feature = "strace")]
+        new_task_ext.strace.store(
+            current_task.task_ext().strace.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+
         new_task_ext.ns_init_new();
         new_task.init_task_ext(new_task_ext);
         let new_task_ref = axtask::spawn_task(new_task);
+        pid::register_task(new_pid, &new_task_ref);
         current_task.task_ext().children.lock().push(new_task_ref);
-        Ok(return_id)
+        Ok(new_pid as u64)
     }
 
     pub(crate) fn clear_child_tid(&self) -> u64 {
@@ -140,11 +195,22 @@ impl TaskExt {
         self.parent_id.load(Ordering::Acquire)
     }
 
-    #[allow(unused)]
     pub(crate) fn set_parent(&self, parent_id: u64) {
         self.parent_id.store(parent_id, Ordering::Release);
     }
 
+    pub(crate) fn get_pgid(&self) -> usize {
+        self.pgid.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_pgid(&self, pgid: usize) {
+        self.pgid.store(pgid, Ordering::Release);
+    }
+
+    pub(crate) fn get_sid(&self) -> usize {
+        self.sid.load(Ordering::Acquire)
+    }
+
     pub(crate) fn ns_init_new(&self) {
         FD_TABLE
             .deref_from(&self.ns)
@@ -155,6 +221,7 @@ impl TaskExt {
         CURRENT_DIR_PATH
             .deref_from(&self.ns)
             .init_new(CURRENT_DIR_PATH.copy_inner());
+        FD_LIMIT.deref_from(&self.ns).set(FD_LIMIT.get());
     }
 
     pub(crate) fn time_stat_from_kernel_to_user(&self, current_tick: usize) {
@@ -171,16 +238,22 @@ impl TaskExt {
         }
     }
 
-    pub(crate) fn time_stat_output(&self) -> (usize, usize) {
+    /// Add the CPU times of a reaped child, including those of its own reaped
+    /// children, to the children times.
+    pub(crate) fn add_child_times(&self, times: CpuTimes) {
         let time = self.time.get();
-        unsafe { (*time).output() }
+        unsafe {
+            (*time).add_child(
+                times.utime.as_nanos() as usize,
+                times.stime.as_nanos() as usize,
+            );
+        }
     }
 
     pub(crate) fn get_heap_bottom(&self) -> u64 {
         self.heap_bottom.load(Ordering::Acquire)
     }
 
-    #[allow(unused)]
     pub(crate) fn set_heap_bottom(&self, bottom: u64) {
         self.heap_bottom.store(bottom, Ordering::Release)
     }
@@ -192,6 +265,38 @@ impl TaskExt {
     pub(crate) fn set_heap_top(&self, top: u64) {
         self.heap_top.store(top, Ordering::Release)
     }
+
+    pub(crate) fn get_rlimit(&self, resource: RLimitResource) -> RLimit {
+        self.rlimits.lock()[resource as usize]
+    }
+
+    /// Set the limit of `resource`, and return the old one.
+    ///
+    /// The new limit is checked against the old one under the same lock, so
+    /// a concurrent update can't slip in between. It fails with `EINVAL` if
+    /// the soft limit is above the hard one, or `EPERM` if the hard limit is
+    /// raised, since there is no privileged process.
+    pub(crate) fn set_rlimit(
+        &self,
+        resource: RLimitResource,
+        rlimit: RLimit,
+    ) -> LinuxResult<RLimit> {
+        let mut rlimits = self.rlimits.lock();
+        let old = rlimits[resource as usize];
+        if rlimit.rlim_cur > rlimit.rlim_max {
+            return Err(LinuxError::EINVAL);
+        }
+        if rlimit.rlim_max > old.rlim_max {
+            return Err(LinuxError::EPERM);
+        }
+        if resource == RLimitResource::NOFILE {
+            FD_LIMIT
+                .deref_from(&self.ns)
+                .set(rlimit.rlim_cur.try_into().unwrap_or(usize::MAX));
+        }
+        rlimits[resource as usize] = rlimit;
+        Ok(old)
+    }
 }
 
 struct AxNamespaceImpl;
@@ -219,11 +324,59 @@ impl AxNamespaceIf for AxNamespaceImpl {
 
 axtask::def_task_ext!(TaskExt);
 
-pub fn spawn_user_task(
+/// Attributes of a user task given at spawning.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskAttrs {
+    /// The CPUs that the task is allowed to run on.
+    pub affinity: AxCpuMask,
+    /// The nice value, ranging from -20 (the highest priority) to 19.
+    pub nice: i8,
+    /// The size of the kernel stack, [`axconfig::plat::KERNEL_STACK_SIZE`] if
+    /// not specified.
+    pub kstack_size: Option<usize>,
+}
+
+impl Default for TaskAttrs {
+    fn default() -> Self {
+        Self {
+            affinity: AxCpuMask::full(),
+            nice: 0,
+            kstack_size: None,
+        }
+    }
+}
+
+/// The reasons why a user task can't be spawned or cloned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnError {
+    /// All the PIDs are in use.
+    NoPid,
+    /// The address space can't be copied.
+    NoMemory,
+    /// The affinity contains no online CPU, or the nice value is out of range.
+    InvalidAttrs,
+    /// The nice value is not 0, but the scheduler has no priorities (e.g.
+    /// `sched_rr`).
+    NoPriority,
+}
+
+/// Spawn a user task with the given attributes.
+///
+/// The affinity is applied before the task is put into a run queue, so it
+/// never runs on a CPU outside of it.
+///
+/// The task is a child of init, so the kernel must free its PID with
+/// [`pid::free_pid`] after joining it.
+pub fn spawn_user_task_with_attrs(
     aspace: Arc<Mutex<AddrSpace>>,
     uctx: UspaceContext,
     heap_bottom: u64,
-) -> AxTaskRef {
+    attrs: TaskAttrs,
+) -> Result<AxTaskRef, SpawnError> {
+    // `AxCpuMask` only holds the CPUs brought up at boot, which are all online.
+    if attrs.affinity.is_empty() || !(-20..=19).contains(&attrs.nice) {
+        return Err(SpawnError::InvalidAttrs);
+    }
     let mut task = TaskInner::new(
         || {
             let curr = axtask::current();
@@ -234,21 +387,30 @@ pub fn spawn_user_task(
                 curr.task_ext().uctx.get_sp(),
                 kstack_top,
             );
+            time_stat_from_kernel_to_user();
             unsafe { curr.task_ext().uctx.enter_uspace(kstack_top) };
         },
         "userboot".into(),
-        axconfig::plat::KERNEL_STACK_SIZE,
+        attrs
+            .kstack_size
+            .unwrap_or(axconfig::plat::KERNEL_STACK_SIZE),
     );
     task.ctx_mut()
         .set_page_table_root(aspace.lock().page_table_root());
-    task.init_task_ext(TaskExt::new(
-        task.id().as_u64() as usize,
-        uctx,
-        aspace,
-        heap_bottom,
-    ));
+    let pid = pid::alloc_pid().ok_or(SpawnError::NoPid)?;
+    task.init_task_ext(TaskExt::new(pid, uctx, aspace, heap_bottom));
     task.task_ext().ns_init_new();
-    axtask::spawn_task(task)
+    task.set_cpumask(attrs.affinity);
+    let task = if attrs.nice == 0 {
+        axtask::spawn_task(task)
+    } else if let Some(task) = axtask::spawn_task_with_priority(task, attrs.nice as _) {
+        task
+    } else {
+        pid::free_pid(pid);
+        return Err(SpawnError::NoPriority);
+    };
+    pid::register_task(pid, &task);
+    Ok(task)
 }
 
 #[allow(unused)]
@@ -266,67 +428,91 @@ pub fn read_trapframe_from_kstack(kstack_top: usize) -> TrapFrame {
     unsafe { *trap_frame_ptr }
 }
 
+/// Wait for a child process selected by `pid` as in `wait4`:
+///
+/// - `pid < -1`: any child in the process group `-pid`;
+/// - `pid == -1`: any child;
+/// - `pid == 0`: any child in the process group of the caller;
+/// - `pid > 0`: the child with the PID `pid`.
+///
+/// If an exited child is found, it's reaped and its PID is freed.
 pub fn wait_pid(pid: i32, exit_code_ptr: *mut i32) -> Result<u64, WaitStatus> {
     let curr_task = current();
-    let mut exit_task_id: usize = 0;
-    let mut answer_id: u64 = 0;
+    let curr_pgid = curr_task.task_ext().get_pgid();
+    let is_selected = |child: &AxTaskRef| match pid {
+        -1 => true,
+        0 => child.task_ext().get_pgid() == curr_pgid,
+        pid if pid > 0 => child.task_ext().proc_id == pid as usize,
+        pid => child.task_ext().get_pgid() == pid.unsigned_abs() as usize,
+    };
+
+    let mut children = curr_task.task_ext().children.lock();
     let mut answer_status = WaitStatus::NotExist;
+    let mut exited_index = None;
+    for (index, child) in children.iter().enumerate() {
+        if !is_selected(child) {
+            continue;
+        }
+        answer_status = WaitStatus::Running;
+        if child.state() == axtask::TaskState::Exited {
+            exited_index = Some(index);
+            break;
+        }
+    }
 
-    for (index, child) in curr_task.task_ext().children.lock().iter().enumerate() {
-        if pid <= 0 {
-            if pid == 0 {
-                warn!("Don't support for process group.");
-            }
-
-            answer_status = WaitStatus::Running;
-            if child.state() == axtask::TaskState::Exited {
-                let exit_code = child.exit_code();
-                answer_status = WaitStatus::Exited;
-                info!(
-                    "wait pid _{}_ with code _{}_",
-                    child.id().as_u64(),
-                    exit_code
-                );
-                exit_task_id = index;
-                if !exit_code_ptr.is_null() {
-                    unsafe {
-                        *exit_code_ptr = exit_code << 8;
-                    }
-                }
-                answer_id = child.id().as_u64();
-                break;
-            }
-        } else if child.id().as_u64() == pid as u64 {
-            if let Some(exit_code) = child.join() {
-                answer_status = WaitStatus::Exited;
-                info!(
-                    "wait pid _{}_ with code _{:?}_",
-                    child.id().as_u64(),
-                    exit_code
-                );
-                exit_task_id = index;
-                if !exit_code_ptr.is_null() {
-                    unsafe {
-                        *exit_code_ptr = exit_code << 8;
-                    }
-                }
-                answer_id = child.id().as_u64();
-            } else {
-                answer_status = WaitStatus::Running;
+    if let Some(index) = exited_index {
+        let child = children.remove(index);
+        drop(children);
+        let exit_code = child.exit_code();
+        let child_pid = child.task_ext().proc_id;
+        let (times, children_times) = cpu_times(&child);
+        curr_task.task_ext().add_child_times(CpuTimes {
+            utime: times.utime + children_times.utime,
+            stime: times.stime + children_times.stime,
+        });
+        info!("wait pid _{}_ with code _{}_", child_pid, exit_code);
+        if !exit_code_ptr.is_null() {
+            unsafe {
+                *exit_code_ptr = exit_code << 8;
             }
-            break;
         }
+        pid::free_pid(child_pid);
+        return Ok(child_pid as u64);
     }
+    drop(children);
 
     if answer_status == WaitStatus::Running {
         axtask::yield_now();
     }
+    Err(answer_status)
+}
 
-    if answer_status == WaitStatus::Exited {
-        curr_task.task_ext().children.lock().remove(exit_task_id);
-        return Ok(answer_id);
+/// The children of exited processes, adopted by init.
+static ORPHANS: Mutex<Vec<AxTaskRef>> = Mutex::new(Vec::new());
+
+/// Exit the current process with `exit_code`.
+///
+/// The children are reparented to init, which reaps them in [`reap_orphans`].
+pub fn exit_current(exit_code: i32) -> ! {
+    let curr = current();
+    let children = core::mem::take(&mut *curr.task_ext().children.lock());
+    for child in &children {
+        child.task_ext().set_parent(INIT_PID as u64);
     }
-    Err(answer_status)
+    ORPHANS.lock().extend(children);
+    drop(curr);
+    axtask::exit(exit_code)
+}
+
+/// Reap the exited orphans and free their PIDs, as init does.
+pub fn reap_orphans() {
+    ORPHANS.lock().retain(|orphan| {
+        let exited = orphan.state() == axtask::TaskState::Exited;
+        if exited {
+            pid::free_pid(orphan.task_ext().proc_id);
+        }
+        !exited
+    });
 }
 
 pub fn exec(name: &str) -> AxResult<()> {
@@ -344,15 +530,18 @@ pub fn exec(name: &str) -> AxResult<()> {
     aspace.unmap_user_areas()?;
     axhal::arch::flush_tlb(None);
     let args = vec![program_name];
-    let (entry_point, user_stack_base) = crate::mm::load_user_app(&mut (args.into()), &mut aspace)
-        .map_err(|_| {
+    let (entry_point, user_stack_base, heap_bottom) =
+        crate::mm::load_user_app(&mut (args.into()), &mut aspace).map_err(|_| {
             error!("Failed to load app {}", name);
             AxError::NotFound
         })?;
     // current_task.set_name(name);
     drop(aspace);
     let task_ext = unsafe { &mut *(current_task.task_ext_ptr() as *mut TaskExt) };
+    task_ext.set_heap_bottom(heap_bottom.as_usize() as u64);
+    task_ext.set_heap_top(heap_bottom.as_usize() as u64);
     task_ext.uctx = UspaceContext::new(entry_point.as_usize(), user_stack_base, 0);
+    time_stat_from_kernel_to_user();
     unsafe {
         task_ext.uctx.enter_uspace(
             current_task
@@ -363,6 +552,64 @@ pub fn exec(name: &str) -> AxResult<()> {
     }
 }
 
+/// The exit code of a killed process, as reported by shells for `SIGKILL`.
+pub const KILLED_EXIT_CODE: i32 = 128 + 9;
+
+/// Kill all the processes in the session `sid`.
+///
+/// There are no signals yet, so a process only exits with
+/// [`KILLED_EXIT_CODE`] when it's about to return to the user space or polls
+/// in `wait4`, see [`exit_if_killed`]. A process blocked in the kernel is
+/// interrupted, so that the blocking call fails with `EINTR` and it returns.
+pub fn kill_session(sid: usize) {
+    for task in pid::all_tasks() {
+        if task.task_ext().get_sid() == sid {
+            task.task_ext().killed.store(true, Ordering::Release);
+            axtask::interrupt(&task);
+        }
+    }
+}
+
+/// Exit the current process if it has been killed by [`kill_session`].
+pub fn exit_if_killed() {
+    let killed = current().task_ext().killed.load(Ordering::Acquire);
+    if killed {
+        exit_current(KILLED_EXIT_CODE);
+    }
+}
+
+/// Account the time in the user space until the trap.
+#[register_trap_handler(USER_ENTRY)]
+fn handle_user_entry() {
+    time_stat_from_user_to_kernel();
+}
+
+/// Give up the CPU before resuming the user task if its time slice has run
+/// out, so that a task spinning in user space can't starve the others.
+///
+/// A killed task exits here instead of resuming.
+#[register_trap_handler(USER_RETURN)]
+fn handle_user_return() {
+    axtask::resched_if_needed();
+    exit_if_killed();
+    time_stat_from_kernel_to_user();
+}
+
+/// Terminate the current task on an exception it can't recover from, e.g. an
+/// illegal instruction, instead of bringing down the kernel.
+#[register_trap_handler(EXCEPTION)]
+fn handle_exception(kind: ExceptionKind, ip: VirtAddr, vaddr: VirtAddr) -> bool {
+    // TODO: send SIGILL, SIGBUS or SIGSEGV when signals are supported.
+    warn!(
+        "{}: {:?} at {:#x}, fault address {:#x}, exit!",
+        current().id_name(),
+        kind,
+        ip,
+        vaddr
+    );
+    exit_current(-1);
+}
+
 pub fn time_stat_from_kernel_to_user() {
     let curr_task = current();
     curr_task
@@ -377,13 +624,33 @@ pub fn time_stat_from_user_to_kernel() {
         .time_stat_from_user_to_kernel(monotonic_time_nanos() as usize);
 }
 
-pub fn time_stat_output() -> (usize, usize, usize, usize) {
-    let curr_task = current();
-    let (utime_ns, stime_ns) = curr_task.task_ext().time_stat_output();
+/// The CPU times of a process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    /// The time spent in the user mode.
+    pub utime: Duration,
+    /// The time spent in the kernel mode.
+    pub stime: Duration,
+}
+
+/// Get the CPU times of the process `task`, and the total CPU times of its
+/// reaped children.
+///
+/// `task` must be the current task or an exited one, whose time statistics
+/// are not being updated.
+pub fn cpu_times(task: &TaskInner) -> (CpuTimes, CpuTimes) {
+    let time = unsafe { &*task.task_ext().time.get() };
+    let utime = Duration::from_nanos(time.utime_ns() as u64);
+    let (cutime_ns, cstime_ns) = time.children_ns();
     (
-        utime_ns / NANOS_PER_SEC as usize,
-        utime_ns / NANOS_PER_MICROS as usize,
-        stime_ns / NANOS_PER_SEC as usize,
-        stime_ns / NANOS_PER_MICROS as usize,
+        CpuTimes {
+            utime,
+            // The time on CPUs not in the user mode is in the kernel mode.
+            stime: task.cpu_time().saturating_sub(utime),
+        },
+        CpuTimes {
+            utime: Duration::from_nanos(cutime_ns as u64),
+            stime: Duration::from_nanos(cstime_ns as u64),
+        },
     )
 }
@@ -0,0 +1,246 @@
+//! The list of testcases to run at boot.
+//!
+//! The list is read from [`TESTCASES_FILE`] on the test filesystem, so that
+//! the suite can be changed without rebuilding the kernel. If the file is
+//! absent, the list given by `AX_TESTCASES_LIST` at compile time is used.
+//!
+//! Each line of the file is a testcase: the path of the executable, optionally
+//! followed by arguments separated by whitespace. Blank lines and lines
+//! starting with `#` are ignored.
+//!
+//! The path can be preceded by options in the form of `key=value`:
+//!
+//! - `timeout=<secs>`: the testcase is killed if it runs longer than `secs`
+//!   seconds, instead of the default given by `AX_TEST_TIMEOUT` at compile
+//!   time, or [`DEFAULT_TIMEOUT_SECS`];
+//! - `expect=<pass|fail|timeout>`: the expected status of the testcase, `pass`
+//!   by default. The kernel exits with a failure if any testcase doesn't end
+//!   as expected;
+//! - `cpu=<id>`: the testcase only runs on the CPU `id`, instead of any CPU.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use axstd::println;
+use axtask::AxCpuMask;
+use core::{fmt, time::Duration};
+
+/// The path of the testcase list on the test filesystem.
+const TESTCASES_FILE: &str = "/testcases.txt";
+
+/// The timeout of a testcase in seconds, if neither the `timeout` option nor
+/// `AX_TEST_TIMEOUT` is given.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// The timeout of the testcases without the `timeout` option.
+fn default_timeout() -> Duration {
+    let secs = option_env!("AX_TEST_TIMEOUT")
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// A testcase to run.
+pub struct Testcase {
+    /// The path of the executable.
+    pub path: String,
+    /// The arguments passed to the executable, including `argv[0]`.
+    pub args: Vec<String>,
+    /// How long the testcase can run before it's killed.
+    pub timeout: Duration,
+    /// The expected status of the testcase.
+    pub expect: Status,
+    /// The CPUs that the testcase can run on.
+    pub affinity: AxCpuMask,
+}
+
+impl Testcase {
+    /// Parse a testcase from an entry of the list, or return `None` if the
+    /// entry is blank or a comment.
+    fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        if entry.starts_with('#') {
+            return None;
+        }
+        let mut timeout = default_timeout();
+        let mut expect = Status::Pass;
+        let mut affinity = AxCpuMask::full();
+        let mut tokens = entry.split_whitespace();
+        let mut path = None;
+        for token in tokens.by_ref() {
+            let Some((key, value)) = token.split_once('=') else {
+                path = Some(token);
+                break;
+            };
+            match key {
+                "timeout" => match value.parse() {
+                    Ok(secs) => timeout = Duration::from_secs(secs),
+                    Err(_) => warn!("Invalid testcase timeout: {:?}", value),
+                },
+                "expect" => match Status::parse(value) {
+                    Some(status) => expect = status,
+                    None => warn!("Invalid testcase status: {:?}", value),
+                },
+                "cpu" => match value.parse() {
+                    Ok(cpu) if cpu < axconfig::SMP => affinity = AxCpuMask::one_shot(cpu),
+                    _ => warn!("Invalid testcase CPU: {:?}", value),
+                },
+                _ => warn!("Unknown testcase option: {:?}", token),
+            }
+        }
+        let args = core::iter::once(path?)
+            .chain(tokens)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let path = args[0].clone();
+        Some(Self {
+            path,
+            args,
+            timeout,
+            expect,
+            affinity,
+        })
+    }
+
+    /// The file name of the executable.
+    pub fn name(&self) -> &str {
+        self.path.split('/').next_back().unwrap()
+    }
+
+    /// The directory containing the executable, empty if the path has no `/`.
+    pub fn dir(&self) -> &str {
+        self.path.rsplit_once('/').map_or("", |(dir, _)| dir)
+    }
+}
+
+/// Load the testcases from [`TESTCASES_FILE`], falling back to the list given
+/// at compile time.
+pub fn load_testcases() -> Vec<Testcase> {
+    match axfs::api::read_to_string(TESTCASES_FILE) {
+        Ok(list) => {
+            info!("Loading testcases from {}", TESTCASES_FILE);
+            // `lines` also strips the `\r` of CRLF line endings.
+            list.lines().filter_map(Testcase::parse).collect()
+        }
+        Err(err) => {
+            info!(
+                "Failed to read {}: {:?}, using the built-in list",
+                TESTCASES_FILE, err
+            );
+            option_env!("AX_TESTCASES_LIST")
+                .unwrap_or_else(|| "Please specify the testcases list by making user_apps")
+                .split(',')
+                .filter_map(Testcase::parse)
+                .collect()
+        }
+    }
+}
+
+/// How a testcase ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The testcase exited with code 0.
+    Pass,
+    /// The testcase exited with a nonzero code, or failed to load.
+    Fail,
+    /// The testcase was killed because it ran out of time.
+    Timeout,
+}
+
+impl Status {
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pass" => Some(Self::Pass),
+            "fail" => Some(Self::Fail),
+            "timeout" => Some(Self::Timeout),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::Pass => "pass",
+            Self::Fail => "fail",
+            Self::Timeout => "timeout",
+        })
+    }
+}
+
+/// The result of running a testcase.
+pub struct TestResult {
+    /// The file name of the executable.
+    pub name: String,
+    /// How the testcase ended.
+    pub status: Status,
+    /// The expected status of the testcase.
+    pub expect: Status,
+    /// The exit code, or the code of a killed or unloadable testcase.
+    pub code: i32,
+    /// The wall-clock time the testcase ran.
+    pub time: Duration,
+}
+
+impl TestResult {
+    /// Print the result in a line that can be parsed by scripts:
+    ///
+    /// ```text
+    /// RESULT name=<name> status=<pass|fail|timeout> code=<code> time_ms=<ms>
+    /// ```
+    pub fn report(&self) {
+        println!(
+            "RESULT name={} status={} code={} time_ms={}",
+            self.name,
+            self.status,
+            self.code,
+            self.time.as_millis()
+        );
+    }
+
+    /// Whether the testcase ended as expected.
+    pub fn is_expected(&self) -> bool {
+        self.status == self.expect
+    }
+}
+
+/// Print a table of `results`, followed by a line of the counts that can be
+/// parsed by scripts:
+///
+/// ```text
+/// SUMMARY total=<n> pass=<n> fail=<n> timeout=<n> unexpected=<n>
+/// ```
+pub fn print_summary(results: &[TestResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.name.len())
+        .fold("TESTCASE".len(), usize::max);
+    println!(
+        "{:<width$}  {:<7}  {:>5}  {:>9}",
+        "TESTCASE", "STATUS", "CODE", "TIME(ms)"
+    );
+    for result in results {
+        println!(
+            "{:<width$}  {:<7}  {:>5}  {:>9}{}",
+            result.name,
+            result.status,
+            result.code,
+            result.time.as_millis(),
+            if result.is_expected() {
+                ""
+            } else {
+                "  (unexpected)"
+            }
+        );
+    }
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    println!(
+        "SUMMARY total={} pass={} fail={} timeout={} unexpected={}",
+        results.len(),
+        count(Status::Pass),
+        count(Status::Fail),
+        count(Status::Timeout),
+        results.iter().filter(|r| !r.is_expected()).count()
+    );
+}
@@ -0,0 +1,100 @@
+//! The console as a terminal.
+//!
+//! Only the requests that libc needs to treat the console as a tty are
+//! supported: the attributes and the window size can be read and written, but
+//! they don't change how the console behaves.
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::Mutex;
+
+use crate::ctypes::{NCCS, Termios, WinSize};
+
+/// Get the terminal attributes.
+const TCGETS: u32 = 0x5401;
+/// Set the terminal attributes now.
+const TCSETS: u32 = 0x5402;
+/// Set the terminal attributes after the output is drained.
+const TCSETSW: u32 = 0x5403;
+/// Set the terminal attributes after the output is drained and discard the
+/// pending input.
+const TCSETSF: u32 = 0x5404;
+/// Get the window size.
+const TIOCGWINSZ: u32 = 0x5413;
+/// Set the window size.
+const TIOCSWINSZ: u32 = 0x5414;
+
+/// The number of rows of the console before any `TIOCSWINSZ`.
+const DEFAULT_ROWS: u16 = 24;
+/// The number of columns of the console before any `TIOCSWINSZ`.
+const DEFAULT_COLS: u16 = 80;
+
+/// The attributes of a cooked terminal, the same as `stty sane` on Linux.
+const DEFAULT_TERMIOS: Termios = {
+    let mut c_cc = [0; NCCS];
+    c_cc[0] = 0x03; // VINTR: ^C
+    c_cc[1] = 0x1c; // VQUIT: ^\
+    c_cc[2] = 0x7f; // VERASE: DEL
+    c_cc[3] = 0x15; // VKILL: ^U
+    c_cc[4] = 0x04; // VEOF: ^D
+    c_cc[5] = 0; // VTIME
+    c_cc[6] = 1; // VMIN
+    c_cc[8] = 0x11; // VSTART: ^Q
+    c_cc[9] = 0x13; // VSTOP: ^S
+    c_cc[10] = 0x1a; // VSUSP: ^Z
+    c_cc[12] = 0x12; // VREPRINT: ^R
+    c_cc[13] = 0x0f; // VDISCARD: ^O
+    c_cc[14] = 0x17; // VWERASE: ^W
+    c_cc[15] = 0x16; // VLNEXT: ^V
+    Termios {
+        c_iflag: 0o2400,   // ICRNL | IXON
+        c_oflag: 0o5,      // OPOST | ONLCR
+        c_cflag: 0o2277,   // B38400 | CS8 | CREAD | HUPCL
+        c_lflag: 0o105073, // ISIG | ICANON | ECHO | ECHOE | ECHOK | ECHOCTL | ECHOKE | IEXTEN
+        c_line: 0,
+        c_cc,
+    }
+};
+
+static TERMIOS: Mutex<Termios> = Mutex::new(DEFAULT_TERMIOS);
+
+static WINSIZE: Mutex<WinSize> = Mutex::new(WinSize {
+    ws_row: DEFAULT_ROWS,
+    ws_col: DEFAULT_COLS,
+    ws_xpixel: 0,
+    ws_ypixel: 0,
+});
+
+/// Handle the `ioctl` request `cmd` on the console with the user pointer
+/// `arg`.
+fn console_ioctl(cmd: u32, arg: usize) -> LinuxResult<usize> {
+    match cmd {
+        TCGETS | TCSETS | TCSETSW | TCSETSF | TIOCGWINSZ | TIOCSWINSZ if arg == 0 => {
+            Err(LinuxError::EFAULT)
+        }
+        TCGETS => {
+            unsafe { *(arg as *mut Termios) = *TERMIOS.lock() };
+            Ok(0)
+        }
+        TCSETS | TCSETSW | TCSETSF => {
+            *TERMIOS.lock() = unsafe { *(arg as *const Termios) };
+            Ok(0)
+        }
+        TIOCGWINSZ => {
+            unsafe { *(arg as *mut WinSize) = *WINSIZE.lock() };
+            Ok(0)
+        }
+        TIOCSWINSZ => {
+            *WINSIZE.lock() = unsafe { *(arg as *const WinSize) };
+            Ok(0)
+        }
+        _ => {
+            debug!("Unsupported ioctl request on the console: {:#x}", cmd);
+            Err(LinuxError::ENOTTY)
+        }
+    }
+}
+
+/// Make the console a terminal by handling its `ioctl` requests.
+pub fn init() {
+    arceos_posix_api::set_console_ioctl(console_ioctl);
+}